@@ -0,0 +1,413 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DeriveInput, Expr, Fields, GenericArgument, LitBool,
+    LitStr, PathArguments, Type,
+};
+
+/// Derives `diswh_esp::IntoEmbed`, turning every field of a struct into an embed field.
+///
+/// Fields are shown with their name in sentence case (`battery_voltage` becomes "Battery voltage") and their
+/// [Display](std::fmt::Display) value, inline by default. `Option` fields are left out while they are `None`.
+///
+/// # Struct attributes
+/// - `#[embed(title = "...")]`, `#[embed(description = "...")]` and `#[embed(footer = "...")]` set fixed text.
+/// - `#[embed(color = ...)]` sets the color, any expression evaluating to an `i32`, such as `Color::GREEN`.
+/// - `#[embed(inline = false)]` changes the default for all fields.
+///
+/// # Field attributes
+/// - `#[embed(name = "...")]` replaces the field name.
+/// - `#[embed(inline = false)]` puts the field on its own line.
+/// - `#[embed(format = "{:.1}")]` formats the value with a format string instead of [Display](std::fmt::Display).
+/// - `#[embed(unit = "°C")]` appends a unit to the value.
+/// - `#[embed(skip)]` leaves the field out.
+/// - `#[embed(title)]`, `#[embed(description)]`, `#[embed(footer)]` and `#[embed(color)]` use the field's value for
+///   that part of the embed instead of adding a field. Color fields must convert into an `i32`.
+///
+/// # Example
+/// ```ignore
+/// use diswh_esp::{Color, IntoEmbed};
+///
+/// #[derive(IntoEmbed)]
+/// #[embed(color = Color::BLUE, footer = "Greenhouse sensors")]
+/// struct Reading {
+///     #[embed(title)]
+///     sensor: String,
+///     #[embed(format = "{:.1}", unit = "°C")]
+///     temperature: f32,
+///     #[embed(unit = "%")]
+///     humidity: u8,
+///     #[embed(name = "Battery", unit = "V", inline = false)]
+///     battery_voltage: Option<f32>,
+///     #[embed(skip)]
+///     raw: [u8; 16],
+/// }
+///
+/// let embed = reading.to_embed();
+/// ```
+#[proc_macro_derive(IntoEmbed, attributes(embed))]
+pub fn derive_into_embed(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+#[derive(Default)]
+struct StructOptions {
+    title: Option<LitStr>,
+    description: Option<LitStr>,
+    footer: Option<LitStr>,
+    color: Option<Expr>,
+    inline: Option<LitBool>,
+}
+
+/// What a field is used for.
+enum Role {
+    Field,
+    Skip,
+    Title,
+    Description,
+    Footer,
+    Color,
+}
+
+struct FieldOptions {
+    role: Role,
+    name: Option<LitStr>,
+    inline: Option<LitBool>,
+    format: Option<LitStr>,
+    unit: Option<LitStr>,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(input.span(), "IntoEmbed can only be derived for structs"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new(
+            input.span(),
+            "IntoEmbed can only be derived for structs with named fields",
+        ));
+    };
+
+    let options = struct_options(&input.attrs)?;
+    let default_inline = options.inline.as_ref().is_none_or(LitBool::value);
+    let mut steps = Vec::new();
+    if let Some(title) = &options.title {
+        steps.push(quote!(builder = builder.with_title(#title);));
+    }
+    if let Some(description) = &options.description {
+        steps.push(quote!(builder = builder.with_description(#description);));
+    }
+    if let Some(footer) = &options.footer {
+        steps.push(quote!(builder = builder.with_footer_text(#footer);));
+    }
+    if let Some(color) = &options.color {
+        steps.push(quote!(builder = builder.with_color(#color);));
+    }
+
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named fields have an ident");
+        let options = field_options(&field.attrs)?;
+        let to_string = quote!(::std::string::ToString::to_string(value));
+        let step = match options.role {
+            Role::Skip => continue,
+            Role::Title => quote!(builder = builder.with_title(#to_string);),
+            Role::Description => quote!(builder = builder.with_description(#to_string);),
+            Role::Footer => quote!(builder = builder.with_footer_text(#to_string);),
+            Role::Color => quote!(
+                builder = builder.with_color(::core::convert::Into::<i32>::into(::core::clone::Clone::clone(value)));
+            ),
+            Role::Field => {
+                let name = match &options.name {
+                    Some(name) => name.value(),
+                    None => sentence_case(&ident.to_string()),
+                };
+                let inline = options.inline.as_ref().map_or(default_inline, LitBool::value);
+                let mut text = match &options.format {
+                    Some(format) => quote!(::std::format!(#format, value)),
+                    None => to_string,
+                };
+                if let Some(unit) = &options.unit {
+                    text = quote!(::std::format!("{} {}", #text, #unit));
+                }
+                quote!(builder = builder.add_field(#name, #text, #inline);)
+            }
+        };
+        steps.push(if is_option(&field.ty) {
+            quote!(if let ::core::option::Option::Some(value) = &self.#ident { #step })
+        } else {
+            quote!({ let value = &self.#ident; #step })
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::diswh_esp::IntoEmbed for #name #type_generics #where_clause {
+            fn to_embed(&self) -> ::diswh_esp::Embed {
+                let mut builder = ::diswh_esp::EmbedBuilder::new();
+                #(#steps)*
+                builder.build()
+            }
+        }
+    })
+}
+
+fn struct_options(attrs: &[Attribute]) -> syn::Result<StructOptions> {
+    let mut options = StructOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("embed")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("title") {
+                options.title = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("description") {
+                options.description = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("footer") {
+                options.footer = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("color") {
+                options.color = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("inline") {
+                options.inline = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `title`, `description`, `footer`, `color` or `inline`"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+fn field_options(attrs: &[Attribute]) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions {
+        role: Role::Field,
+        name: None,
+        inline: None,
+        format: None,
+        unit: None,
+    };
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("embed")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                options.name = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("inline") {
+                options.inline = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("format") {
+                options.format = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("unit") {
+                options.unit = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("skip") {
+                options.role = Role::Skip;
+            } else if meta.path.is_ident("title") {
+                options.role = Role::Title;
+            } else if meta.path.is_ident("description") {
+                options.role = Role::Description;
+            } else if meta.path.is_ident("footer") {
+                options.role = Role::Footer;
+            } else if meta.path.is_ident("color") {
+                options.role = Role::Color;
+            } else {
+                return Err(meta.error(
+                    "expected `name`, `inline`, `format`, `unit`, `skip`, `title`, `description`, `footer` or `color`",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(options)
+}
+
+/// Returns whether `ty` is written as an `Option<T>`.
+fn is_option(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.path.segments.last().is_some_and(|segment| {
+        segment.ident == "Option"
+            && matches!(
+                &segment.arguments,
+                PathArguments::AngleBracketed(arguments)
+                    if matches!(arguments.args.first(), Some(GenericArgument::Type(_)))
+            )
+    })
+}
+
+/// Turns `battery_voltage` into "Battery voltage".
+fn sentence_case(ident: &str) -> String {
+    let words = ident.trim_start_matches("r#").replace('_', " ");
+    let mut chars = words.trim().chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn assert_expands(input: DeriveInput, steps: TokenStream2) {
+        let name = &input.ident;
+        let expected = quote! {
+            impl ::diswh_esp::IntoEmbed for #name {
+                fn to_embed(&self) -> ::diswh_esp::Embed {
+                    let mut builder = ::diswh_esp::EmbedBuilder::new();
+                    #steps
+                    builder.build()
+                }
+            }
+        };
+        assert_eq!(expand(input).unwrap().to_string(), expected.to_string());
+    }
+
+    fn expand_error(input: DeriveInput) -> String {
+        expand(input).map(|_| ()).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn expands_field_attributes() {
+        assert_expands(
+            parse_quote! {
+                struct Reading {
+                    battery_voltage: f32,
+                    #[embed(name = "Temp", format = "{:.1}", unit = "°C", inline = false)]
+                    temperature: f32,
+                    #[embed(unit = "%")]
+                    humidity: Option<u8>,
+                    #[embed(skip)]
+                    raw: [u8; 16],
+                }
+            },
+            quote! {
+                {
+                    let value = &self.battery_voltage;
+                    builder = builder.add_field("Battery voltage", ::std::string::ToString::to_string(value), true);
+                }
+                {
+                    let value = &self.temperature;
+                    builder = builder.add_field("Temp", ::std::format!("{} {}", ::std::format!("{:.1}", value), "°C"), false);
+                }
+                if let ::core::option::Option::Some(value) = &self.humidity {
+                    builder = builder.add_field("Humidity", ::std::format!("{} {}", ::std::string::ToString::to_string(value), "%"), true);
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn expands_field_roles() {
+        assert_expands(
+            parse_quote! {
+                struct Status {
+                    #[embed(title)]
+                    device: String,
+                    #[embed(description)]
+                    summary: Option<String>,
+                    #[embed(footer)]
+                    firmware: String,
+                    #[embed(color)]
+                    color: Color,
+                }
+            },
+            quote! {
+                {
+                    let value = &self.device;
+                    builder = builder.with_title(::std::string::ToString::to_string(value));
+                }
+                if let ::core::option::Option::Some(value) = &self.summary {
+                    builder = builder.with_description(::std::string::ToString::to_string(value));
+                }
+                {
+                    let value = &self.firmware;
+                    builder = builder.with_footer_text(::std::string::ToString::to_string(value));
+                }
+                {
+                    let value = &self.color;
+                    builder = builder.with_color(::core::convert::Into::<i32>::into(::core::clone::Clone::clone(value)));
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn expands_struct_options() {
+        assert_expands(
+            parse_quote! {
+                #[embed(title = "Greenhouse", description = "Sensors", footer = "v1")]
+                #[embed(color = Color::GREEN, inline = false)]
+                struct Reading {
+                    #[embed(inline = true)]
+                    temperature: f32,
+                    humidity: u8,
+                }
+            },
+            quote! {
+                builder = builder.with_title("Greenhouse");
+                builder = builder.with_description("Sensors");
+                builder = builder.with_footer_text("v1");
+                builder = builder.with_color(Color::GREEN);
+                {
+                    let value = &self.temperature;
+                    builder = builder.add_field("Temperature", ::std::string::ToString::to_string(value), true);
+                }
+                {
+                    let value = &self.humidity;
+                    builder = builder.add_field("Humidity", ::std::string::ToString::to_string(value), false);
+                }
+            },
+        );
+    }
+
+    #[test]
+    fn keeps_generics() {
+        let input: DeriveInput = parse_quote! {
+            struct Reading<T: Display> where T: Clone {
+                value: T,
+            }
+        };
+        let expanded = expand(input).unwrap().to_string();
+        let expected = quote!(impl<T: Display> ::diswh_esp::IntoEmbed for Reading<T> where T: Clone).to_string();
+        assert!(expanded.starts_with(&expected), "{}", expanded);
+    }
+
+    #[test]
+    fn rejects_enums_and_tuple_structs() {
+        assert_eq!(
+            expand_error(parse_quote!(enum State { On, Off })),
+            "IntoEmbed can only be derived for structs"
+        );
+        assert_eq!(
+            expand_error(parse_quote!(struct Reading(f32);)),
+            "IntoEmbed can only be derived for structs with named fields"
+        );
+        assert_eq!(
+            expand_error(parse_quote!(struct Empty;)),
+            "IntoEmbed can only be derived for structs with named fields"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_attributes() {
+        let error = expand_error(parse_quote! {
+            #[embed(colour = 5)]
+            struct Reading { value: f32 }
+        });
+        assert!(error.starts_with("expected `title`"), "{}", error);
+        let error = expand_error(parse_quote! {
+            struct Reading { #[embed(units = "V")] value: f32 }
+        });
+        assert!(error.starts_with("expected `name`"), "{}", error);
+        let error = expand_error(parse_quote! {
+            struct Reading { #[embed(inline = "no")] value: f32 }
+        });
+        assert_eq!(error, "expected boolean literal");
+    }
+
+    #[test]
+    fn names_fields_in_sentence_case() {
+        assert_eq!(sentence_case("battery_voltage"), "Battery voltage");
+        assert_eq!(sentence_case("r#type"), "Type");
+        assert_eq!(sentence_case("_private"), "Private");
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use anyhow::bail;
+use log::warn;
+
+use super::{util::format_duration, MessageBuilder, MessagePacket, WebhookBuilder, WebhookResponse};
+
+struct AlertState {
+    window_start: Instant,
+    suppressed: u32,
+}
+
+/// Sends a message on behalf of [Alerts].
+type Sender<'a> = dyn FnMut(&MessagePacket) -> anyhow::Result<WebhookResponse> + 'a;
+
+/// Deduplicates alerts from flapping sources.
+///
+/// Alerts are keyed by a fingerprint chosen by the caller, such as `"boiler/overtemp"`. The first time an alert is
+/// raised it is sent right away, repeats within the window are counted instead of sent, and once the window is over a
+/// "repeated N times" summary is sent. Resolving an alert sends a resolved message and forgets it.
+///
+/// An alert is only recorded as sent or resolved once discord accepted the message. While discord is rate limiting the
+/// webhook nothing is sent, and the alert is left as it was so raising or resolving it again retries.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use diswh_esp::{AlertManager, MessageBuilder, WebhookBuilder};
+///
+/// let mut alerts = AlertManager::new(WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"))
+///     .with_window(Duration::from_secs(600));
+///
+/// # let temperature = 0.0;
+/// if temperature > 90.0 {
+///     alerts.raise("boiler/overtemp", MessageBuilder::new("Boiler over temperature!", false).build())?;
+/// } else {
+///     alerts.resolve("boiler/overtemp")?;
+/// }
+/// // Call regularly so summaries go out even when the alert stops being raised.
+/// alerts.flush()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct AlertManager {
+    webhook: WebhookBuilder,
+    alerts: Alerts,
+}
+
+impl AlertManager {
+    /// Constructs an alert manager with a window of 5 minutes.
+    pub fn new(webhook: WebhookBuilder) -> Self {
+        Self {
+            webhook,
+            alerts: Alerts {
+                window: Duration::from_secs(300),
+                active: HashMap::new(),
+                blocked_until: None,
+            },
+        }
+    }
+
+    /// Sets how long repeats of an alert are suppressed for after it was sent.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.alerts.window = window;
+        self
+    }
+
+    /// Raises an alert, sending `packet` unless the same fingerprint was sent within the window.
+    ///
+    /// When the window is over and repeats were suppressed, the summary is sent ahead of `packet`.
+    /// Returns whether `packet` was sent, a status other than 2xx or 429 is an error.
+    pub fn raise(&mut self, fingerprint: impl Into<String>, packet: MessagePacket) -> anyhow::Result<bool> {
+        let webhook = &self.webhook;
+        let send = &mut |packet: &MessagePacket| webhook.send_message_with_response(packet);
+        self.alerts.raise(fingerprint.into(), &packet, Instant::now(), send)
+    }
+
+    /// Resolves an alert, sending a resolved message if it was active.
+    ///
+    /// See [AlertManager::resolve_with].
+    pub fn resolve(&mut self, fingerprint: &str) -> anyhow::Result<bool> {
+        self.resolve_with(
+            fingerprint,
+            MessageBuilder::new(format!("Resolved: {}", fingerprint), false).build(),
+        )
+    }
+
+    /// Resolves an alert, sending `packet` if it was active.
+    ///
+    /// Returns whether the alert was active and is now resolved. It stays active if `packet` could not be sent.
+    pub fn resolve_with(&mut self, fingerprint: &str, packet: MessagePacket) -> anyhow::Result<bool> {
+        let webhook = &self.webhook;
+        let send = &mut |packet: &MessagePacket| webhook.send_message_with_response(packet);
+        self.alerts.resolve(fingerprint, &packet, Instant::now(), send)
+    }
+
+    /// Sends the summaries of alerts whose window is over and starts a new window for them.
+    ///
+    /// Call this regularly, otherwise a summary is only sent when the alert is raised or resolved again.
+    pub fn flush(&mut self) -> anyhow::Result<()> {
+        let webhook = &self.webhook;
+        let send = &mut |packet: &MessagePacket| webhook.send_message_with_response(packet);
+        self.alerts.flush(Instant::now(), send)
+    }
+
+    /// Returns whether an alert is currently raised.
+    pub fn is_active(&self, fingerprint: &str) -> bool {
+        self.alerts.active.contains_key(fingerprint)
+    }
+}
+
+/// The bookkeeping behind an [AlertManager], given the time and a way to send so it does not depend on either.
+struct Alerts {
+    window: Duration,
+    active: HashMap<String, AlertState>,
+    blocked_until: Option<Instant>,
+}
+
+impl Alerts {
+    fn raise(
+        &mut self,
+        fingerprint: String,
+        packet: &MessagePacket,
+        now: Instant,
+        send: &mut Sender,
+    ) -> anyhow::Result<bool> {
+        if let Some(state) = self.active.get_mut(&fingerprint) {
+            if now.saturating_duration_since(state.window_start) < self.window {
+                state.suppressed += 1;
+                return Ok(false);
+            }
+        }
+        if !self.send_summary(&fingerprint, now, send)? || !self.send(packet, now, send)? {
+            return Ok(false);
+        }
+        self.active.insert(
+            fingerprint,
+            AlertState {
+                window_start: now,
+                suppressed: 0,
+            },
+        );
+        Ok(true)
+    }
+
+    fn resolve(
+        &mut self,
+        fingerprint: &str,
+        packet: &MessagePacket,
+        now: Instant,
+        send: &mut Sender,
+    ) -> anyhow::Result<bool> {
+        if !self.active.contains_key(fingerprint) {
+            return Ok(false);
+        }
+        if !self.send_summary(fingerprint, now, send)? || !self.send(packet, now, send)? {
+            return Ok(false);
+        }
+        self.active.remove(fingerprint);
+        Ok(true)
+    }
+
+    fn flush(&mut self, now: Instant, send: &mut Sender) -> anyhow::Result<()> {
+        let mut due: Vec<String> = self
+            .active
+            .iter()
+            .filter(|(_, state)| {
+                state.suppressed > 0 && now.saturating_duration_since(state.window_start) >= self.window
+            })
+            .map(|(fingerprint, _)| fingerprint.clone())
+            .collect();
+        due.sort();
+        for fingerprint in due {
+            if !self.send_summary(&fingerprint, now, send)? {
+                break;
+            }
+            if let Some(state) = self.active.get_mut(&fingerprint) {
+                state.window_start = now;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the "repeated N times" summary of an alert, if any repeats were suppressed.
+    ///
+    /// Returns false if the summary is still owed because discord is rate limiting the webhook.
+    fn send_summary(&mut self, fingerprint: &str, now: Instant, send: &mut Sender) -> anyhow::Result<bool> {
+        let Some(state) = self.active.get(fingerprint) else {
+            return Ok(true);
+        };
+        if state.suppressed == 0 {
+            return Ok(true);
+        }
+        let summary = format!(
+            "{} repeated {} times in the last {}",
+            fingerprint,
+            state.suppressed,
+            format_duration(now.saturating_duration_since(state.window_start))
+        );
+        if !self.send(&MessageBuilder::new(summary, false).build(), now, send)? {
+            return Ok(false);
+        }
+        if let Some(state) = self.active.get_mut(fingerprint) {
+            state.suppressed = 0;
+        }
+        Ok(true)
+    }
+
+    /// Sends `packet`, returning false without sending while discord is rate limiting the webhook.
+    fn send(&mut self, packet: &MessagePacket, now: Instant, send: &mut Sender) -> anyhow::Result<bool> {
+        if self.blocked_until.is_some_and(|until| now < until) {
+            return Ok(false);
+        }
+        let response = send(packet)?;
+        if response.status == 429 {
+            // Without a usable retry_after, back off for a second like discord's usual per-webhook limit.
+            let retry_after = response.retry_after().unwrap_or(Duration::from_secs(1));
+            warn!("Alerts rate limited, retrying in {:.1}s", retry_after.as_secs_f64());
+            self.blocked_until = Some(now + retry_after);
+            return Ok(false);
+        }
+        if !response.is_success() {
+            bail!("sending alert failed with status {}", response.status);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers every request with the next of `statuses`, then with 204, and records the content of what was sent.
+    struct FakeDiscord {
+        statuses: Vec<u16>,
+        sent: Vec<String>,
+    }
+
+    impl FakeDiscord {
+        fn new(statuses: &[u16]) -> Self {
+            Self {
+                statuses: statuses.iter().rev().copied().collect(),
+                sent: Vec::new(),
+            }
+        }
+
+        fn send(&mut self, packet: &MessagePacket) -> anyhow::Result<WebhookResponse> {
+            self.sent.push(packet.content.clone());
+            let status = self.statuses.pop().unwrap_or(204);
+            let body = if status == 429 { br#"{"retry_after":2}"#.to_vec() } else { Vec::new() };
+            Ok(WebhookResponse {
+                status,
+                body,
+                truncated: false,
+            })
+        }
+
+        fn take(&mut self) -> Vec<String> {
+            std::mem::take(&mut self.sent)
+        }
+    }
+
+    fn alerts() -> Alerts {
+        Alerts {
+            window: Duration::from_secs(60),
+            active: HashMap::new(),
+            blocked_until: None,
+        }
+    }
+
+    fn packet(content: &str) -> MessagePacket {
+        MessageBuilder::new(content, false).build()
+    }
+
+    fn seconds(start: Instant, seconds: u64) -> Instant {
+        start + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn suppresses_repeats_within_the_window() {
+        let mut discord = FakeDiscord::new(&[]);
+        let send = &mut |packet: &MessagePacket| discord.send(packet);
+        let (mut alerts, start) = (alerts(), Instant::now());
+
+        assert!(alerts.raise("boiler".into(), &packet("Boiler hot"), start, send).unwrap());
+        assert!(!alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 10), send).unwrap());
+        assert!(!alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 59), send).unwrap());
+        assert!(alerts.raise("pump".into(), &packet("Pump stopped"), seconds(start, 59), send).unwrap());
+        assert_eq!(alerts.active["boiler"].suppressed, 2);
+        assert_eq!(discord.take(), ["Boiler hot", "Pump stopped"]);
+    }
+
+    #[test]
+    fn summarizes_repeats_once_the_window_expires() {
+        let mut discord = FakeDiscord::new(&[]);
+        let (mut alerts, start) = (alerts(), Instant::now());
+        {
+            let send = &mut |packet: &MessagePacket| discord.send(packet);
+            alerts.raise("boiler".into(), &packet("Boiler hot"), start, send).unwrap();
+            for second in 1..=3 {
+                alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, second), send).unwrap();
+            }
+            alerts.flush(seconds(start, 59), send).unwrap();
+        }
+        assert_eq!(discord.take(), ["Boiler hot"], "nothing is due before the window is over");
+
+        {
+            let send = &mut |packet: &MessagePacket| discord.send(packet);
+            alerts.flush(seconds(start, 90), send).unwrap();
+            alerts.flush(seconds(start, 91), send).unwrap();
+        }
+        assert_eq!(discord.take(), ["boiler repeated 3 times in the last 90 seconds"]);
+        assert_eq!(alerts.active["boiler"].suppressed, 0);
+        assert_eq!(alerts.active["boiler"].window_start, seconds(start, 90));
+
+        // Raising after the window sends the summary of the repeats since ahead of the alert.
+        {
+            let send = &mut |packet: &MessagePacket| discord.send(packet);
+            alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 100), send).unwrap();
+            assert!(alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 150), send).unwrap());
+        }
+        assert_eq!(discord.take(), ["boiler repeated 1 times in the last 60 seconds", "Boiler hot"]);
+    }
+
+    #[test]
+    fn resolves_suppressed_alerts_after_their_summary() {
+        let mut discord = FakeDiscord::new(&[]);
+        let send = &mut |packet: &MessagePacket| discord.send(packet);
+        let (mut alerts, start) = (alerts(), Instant::now());
+
+        assert!(!alerts.resolve("boiler", &packet("Resolved"), start, send).unwrap());
+        alerts.raise("boiler".into(), &packet("Boiler hot"), start, send).unwrap();
+        alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 5), send).unwrap();
+        assert!(alerts.resolve("boiler", &packet("Resolved"), seconds(start, 10), send).unwrap());
+        assert!(!alerts.active.contains_key("boiler"));
+        assert!(alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 11), send).unwrap());
+        assert_eq!(
+            discord.take(),
+            ["Boiler hot", "boiler repeated 1 times in the last 10 seconds", "Resolved", "Boiler hot"]
+        );
+    }
+
+    #[test]
+    fn keeps_state_until_discord_accepts() {
+        let mut discord = FakeDiscord::new(&[429, 500]);
+        let (mut alerts, start) = (alerts(), Instant::now());
+        {
+            let send = &mut |packet: &MessagePacket| discord.send(packet);
+            assert!(!alerts.raise("boiler".into(), &packet("Boiler hot"), start, send).unwrap());
+            assert!(!alerts.active.contains_key("boiler"), "rate limited alerts are not recorded");
+            // Blocked for the 2 seconds of retry_after, without even trying.
+            assert!(!alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 1), send).unwrap());
+            assert!(alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 2), send).is_err());
+            assert!(!alerts.active.contains_key("boiler"));
+            assert!(alerts.raise("boiler".into(), &packet("Boiler hot"), seconds(start, 3), send).unwrap());
+        }
+        assert_eq!(discord.take(), ["Boiler hot", "Boiler hot", "Boiler hot"]);
+
+        discord.statuses = vec![429];
+        {
+            let send = &mut |packet: &MessagePacket| discord.send(packet);
+            assert!(!alerts.resolve("boiler", &packet("Resolved"), seconds(start, 4), send).unwrap());
+            assert!(alerts.active.contains_key("boiler"), "alerts stay active until the resolve is sent");
+            assert!(alerts.resolve("boiler", &packet("Resolved"), seconds(start, 6), send).unwrap());
+        }
+        assert_eq!(discord.take(), ["Resolved", "Resolved"]);
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::bail;
+use serde::{ser::SerializeSeq, Serialize, Serializer};
+
+/// A file uploaded along with a message, such as a chart rendered on the device.
+///
+/// Embeds and components show it through [Attachment::url], as `attachment://chart.png`.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{Attachment, EmbedBuilder, MessageBuilder};
+///
+/// let csv = Attachment::new("readings.csv", "text/csv", b"time,temperature\n0,21.5\n".to_vec());
+/// let message = MessageBuilder::new("", false)
+///     .add_embed(EmbedBuilder::new().with_title("Readings").build())
+///     .add_file(csv)
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Attachment {
+    pub filename: String,
+    /// The MIME type of the data, such as `image/png`.
+    pub content_type: String,
+    /// Alt text shown for images.
+    pub description: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Attachment {
+    /// Constructs an attachment from the raw bytes of a file.
+    ///
+    /// # Panics
+    /// Will panic if `filename` is empty or holds quotes, line breaks or slashes, or if `content_type` is empty or holds
+    /// line breaks.
+    pub fn new(filename: impl Into<String>, content_type: impl Into<String>, data: Vec<u8>) -> Self {
+        match Self::try_new(filename, content_type, data) {
+            Ok(attachment) => attachment,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Constructs an attachment from the raw bytes of a file, failing instead of panicking on an invalid filename or
+    /// content type, such as a filename that came from config.
+    pub fn try_new(
+        filename: impl Into<String>,
+        content_type: impl Into<String>,
+        data: Vec<u8>,
+    ) -> anyhow::Result<Self> {
+        let attachment = Self {
+            filename: filename.into(),
+            content_type: content_type.into(),
+            description: None,
+            data,
+        };
+        attachment.validate()?;
+        Ok(attachment)
+    }
+
+    /// Sets the alt text shown for images.
+    ///
+    /// # Panics
+    /// Will panic if the provided `description` can not be converted into a [String]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// The url embeds and components use to show this attachment, such as `attachment://chart.png`.
+    pub fn url(&self) -> String {
+        format!("attachment://{}", self.filename)
+    }
+
+    /// Checks that the filename and content type can be written into the headers of a multipart body.
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if self.filename.is_empty() || self.filename.contains(['"', '\r', '\n', '/', '\\']) {
+            bail!("invalid attachment filename {:?}", self.filename);
+        }
+        if self.content_type.is_empty() || self.content_type.contains(['\r', '\n']) {
+            bail!("invalid attachment content type {:?}", self.content_type);
+        }
+        Ok(())
+    }
+}
+
+/// Serializes the `attachments` of a message, the metadata discord matches to the uploaded `files[n]` parts.
+pub(crate) fn serialize_metadata<S: Serializer>(files: &[Attachment], serializer: S) -> Result<S::Ok, S::Error> {
+    #[derive(Serialize)]
+    struct Metadata<'a> {
+        id: usize,
+        filename: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<&'a str>,
+    }
+
+    let mut seq = serializer.serialize_seq(Some(files.len()))?;
+    for (id, file) in files.iter().enumerate() {
+        seq.serialize_element(&Metadata {
+            id,
+            filename: &file.filename,
+            description: file.description.as_deref(),
+        })?;
+    }
+    seq.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "invalid attachment content type")]
+    fn rejects_line_breaks_in_the_content_type() {
+        Attachment::new("chart.png", "image/png\r\nX-Injected: 1", Vec::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid attachment filename")]
+    fn rejects_slashes_in_the_filename() {
+        Attachment::new("../chart.png", "image/png", Vec::new());
+    }
+
+    #[test]
+    fn try_new_returns_errors() {
+        let error = Attachment::try_new("", "image/png", Vec::new())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "invalid attachment filename \"\"");
+        assert!(Attachment::try_new("chart\".png", "image/png", Vec::new()).is_err());
+        assert!(Attachment::try_new("chart.png", "", Vec::new()).is_err());
+
+        let attachment = Attachment::try_new("chart.png", "image/png", vec![1, 2, 3]).unwrap();
+        assert_eq!(attachment, Attachment::new("chart.png", "image/png", vec![1, 2, 3]));
+        assert_eq!(attachment.url(), "attachment://chart.png");
+    }
+}
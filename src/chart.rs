@@ -0,0 +1,451 @@
+use super::{attachment::Attachment, color::Color, png};
+
+/// The margin around the plot, in pixels.
+const PADDING: u32 = 4;
+/// The most series a chart holds, keeping its palette within the 256 colors of a PNG.
+const LAYER_LIMIT: usize = 64;
+/// The largest width or height of a chart, in pixels, keeping the memory needed to render it within reach.
+const SIZE_LIMIT: u32 = 1024;
+/// How strongly a band's color shows over the background, out of 255.
+const BAND_OPACITY: u32 = 90;
+
+#[derive(Clone, Debug)]
+enum Layer {
+    Line(Vec<f32>),
+    Bars(Vec<f32>),
+    Band(Vec<(f32, f32)>),
+}
+
+/// Renders a time series into a small PNG, to attach to a message and show in an embed.
+///
+/// Series are drawn as lines, bars or min/max bands, in the order they were added and sharing one value range.
+/// Values that are not finite, such as `f32::NAN` for a missed reading, leave a gap. There are no labels, put the
+/// range and latest values into the embed's fields instead.
+///
+/// Rendering takes about two bytes of memory per pixel, one for the pixels and one for the copy of them the PNG encoder
+/// compresses, plus the encoded image. A 480 by 160 chart needs about 150 KiB.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{Chart, Color, EmbedBuilder, MessageBuilder, WebhookBuilder};
+///
+/// # let hourly: Vec<(f32, f32, f32)> = Vec::new();
+/// // The minimum, average and maximum temperature of each of the last 24 hours.
+/// let chart = Chart::new(480, 160)
+///     .add_band(hourly.iter().map(|(min, _, max)| (*min, *max)), Color::ORANGE)
+///     .add_line(hourly.iter().map(|(_, average, _)| *average), Color::ORANGE)
+///     .to_attachment("chart.png");
+///
+/// let embed = EmbedBuilder::new()
+///     .with_title("Daily summary")
+///     .with_image(chart.url())
+///     .build();
+/// WebhookBuilder::new("https://discord.com/api/webhooks/1234/token")
+///     .send_message(MessageBuilder::new("", false).add_embed(embed).add_file(chart).build())?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Chart {
+    width: u32,
+    height: u32,
+    background: i32,
+    axis: i32,
+    range: Option<(f32, f32)>,
+    layers: Vec<(Layer, i32)>,
+}
+
+impl Chart {
+    /// Constructs an empty chart of `width` by `height` pixels, on discord's dark background.
+    ///
+    /// # Panics
+    /// Will panic if `width` or `height` is 0 or larger than 1024.
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(
+            (1..=SIZE_LIMIT).contains(&width) && (1..=SIZE_LIMIT).contains(&height),
+            "charts are 1 to {} pixels wide and high",
+            SIZE_LIMIT
+        );
+        Self {
+            width,
+            height,
+            background: 0x2b2d31,
+            axis: Color::DARKER_GREY,
+            range: None,
+            layers: Vec::new(),
+        }
+    }
+
+    /// A small line chart of `values`, to show a trend at a glance.
+    ///
+    /// # Example
+    /// ```
+    /// use diswh_esp::Chart;
+    ///
+    /// let png = Chart::sparkline([21.5, 21.8, 22.4, 23.1, 22.7]).to_png();
+    /// assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    /// ```
+    pub fn sparkline(values: impl IntoIterator<Item = f32>) -> Self {
+        Self::new(160, 40).add_line(values, Color::BLUE)
+    }
+
+    /// Sets the background color.
+    pub fn with_background(mut self, color: i32) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Sets the color of the axis, drawn at zero when it is in range and along the bottom otherwise.
+    pub fn with_axis(mut self, color: i32) -> Self {
+        self.axis = color;
+        self
+    }
+
+    /// Fixes the range of values shown, instead of fitting it around the values.
+    ///
+    /// # Panics
+    /// Will panic if `min` is not less than `max`.
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        assert!(min < max, "the minimum of a range must be less than its maximum");
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Adds a line through `values`, spread evenly across the width.
+    ///
+    /// # Panics
+    /// Will panic if the chart already holds 64 series.
+    pub fn add_line(self, values: impl IntoIterator<Item = f32>, color: i32) -> Self {
+        self.add_layer(Layer::Line(values.into_iter().collect()), color)
+    }
+
+    /// Adds a bar for each of `values`, rising from zero or from the bottom when zero is out of range.
+    ///
+    /// # Panics
+    /// Will panic if the chart already holds 64 series.
+    pub fn add_bars(self, values: impl IntoIterator<Item = f32>, color: i32) -> Self {
+        self.add_layer(Layer::Bars(values.into_iter().collect()), color)
+    }
+
+    /// Adds a faint band between each `(min, max)` pair, spread evenly across the width.
+    ///
+    /// # Panics
+    /// Will panic if the chart already holds 64 series.
+    pub fn add_band(self, ranges: impl IntoIterator<Item = (f32, f32)>, color: i32) -> Self {
+        self.add_layer(Layer::Band(ranges.into_iter().collect()), color)
+    }
+
+    fn add_layer(mut self, layer: Layer, color: i32) -> Self {
+        assert!(self.layers.len() < LAYER_LIMIT, "a chart holds at most {} series", LAYER_LIMIT);
+        self.layers.push((layer, color));
+        self
+    }
+
+    /// Encodes the chart as a PNG image.
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut canvas = Canvas {
+            width: self.width,
+            height: self.height,
+            pixels: vec![0; self.width as usize * self.height as usize],
+            palette: vec![rgb(self.background)],
+        };
+        let plot = Plot::new(self.width, self.height, self.range());
+
+        let axis = canvas.color(rgb(self.axis));
+        let axis_y = if plot.min <= 0.0 && 0.0 <= plot.max { plot.y(0.0) } else { plot.bottom };
+        canvas.fill(plot.left, axis_y, plot.right, axis_y, axis);
+
+        for (layer, color) in &self.layers {
+            match layer {
+                Layer::Line(values) => {
+                    let color = canvas.color(rgb(*color));
+                    // Each point connects to the next one, or is drawn on its own when the next is missing.
+                    for (index, value) in values.iter().enumerate().filter(|(_, value)| value.is_finite()) {
+                        let (x, y) = (plot.x(index, values.len()), plot.y(*value));
+                        match values.get(index + 1) {
+                            Some(next) if next.is_finite() => {
+                                canvas.line(x, y, plot.x(index + 1, values.len()), plot.y(*next), color)
+                            }
+                            _ => canvas.line(x, y, x, y, color),
+                        }
+                    }
+                }
+                Layer::Bars(values) => {
+                    let color = canvas.color(rgb(*color));
+                    let width = (plot.right - plot.left + 1) as u64;
+                    let count = values.len() as u64;
+                    for (index, value) in values.iter().enumerate().filter(|(_, value)| value.is_finite()) {
+                        let start = plot.left + (width * index as u64 / count) as u32;
+                        let end = plot.left + (width * (index as u64 + 1) / count) as u32;
+                        // Leave a gap of an eighth of the slot on either side.
+                        let gap = (end - start) / 8;
+                        let top = plot.y(*value);
+                        let right = (end - gap).saturating_sub(1).max(start + gap);
+                        canvas.fill(start + gap, top.min(axis_y), right, top.max(axis_y), color);
+                    }
+                }
+                Layer::Band(ranges) => {
+                    let color = canvas.color(blend(rgb(*color), rgb(self.background), BAND_OPACITY));
+                    let x = |index| plot.x(index, ranges.len());
+                    for (index, pair) in ranges.windows(2).enumerate() {
+                        let [(min0, max0), (min1, max1)] = [pair[0], pair[1]];
+                        if ![min0, max0, min1, max1].iter().all(|value| value.is_finite()) {
+                            continue;
+                        }
+                        let (x0, x1) = (x(index), x(index + 1));
+                        for column in x0..=x1 {
+                            let t = if x1 > x0 { (column - x0) as f32 / (x1 - x0) as f32 } else { 0.0 };
+                            let min = plot.y(min0 + (min1 - min0) * t);
+                            let max = plot.y(max0 + (max1 - max0) * t);
+                            canvas.fill(column, min.min(max), column, min.max(max), color);
+                        }
+                    }
+                }
+            }
+        }
+
+        png::encode(self.width, self.height, &canvas.palette, &canvas.pixels)
+    }
+
+    /// Encodes the chart as a PNG attachment, for [super::MessageBuilder::add_file].
+    ///
+    /// # Panics
+    /// Will panic if `filename` is not a valid attachment filename, see [Attachment::new].
+    pub fn to_attachment(&self, filename: impl Into<String>) -> Attachment {
+        Attachment::new(filename, "image/png", self.to_png())
+    }
+
+    /// The range of values shown, fitted around the values with a small margin unless it was set.
+    fn range(&self) -> (f32, f32) {
+        if let Some(range) = self.range {
+            return range;
+        }
+        let mut values = Vec::new();
+        for (layer, _) in &self.layers {
+            match layer {
+                Layer::Line(series) => values.extend(series),
+                // Bars rise from zero, so it is always in range.
+                Layer::Bars(series) => values.extend(series.iter().chain([&0.0])),
+                Layer::Band(ranges) => values.extend(ranges.iter().flat_map(|(min, max)| [*min, *max])),
+            }
+        }
+        let (min, max) = values
+            .into_iter()
+            .filter(|value| value.is_finite())
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)));
+        if min > max {
+            (0.0, 1.0)
+        } else if min == max {
+            (min - 1.0, max + 1.0)
+        } else {
+            let margin = (max - min) * 0.05;
+            (min - margin, max + margin)
+        }
+    }
+}
+
+/// Maps values onto the pixels of the plot area, inside the padding.
+struct Plot {
+    left: u32,
+    right: u32,
+    top: u32,
+    bottom: u32,
+    min: f32,
+    max: f32,
+}
+
+impl Plot {
+    fn new(width: u32, height: u32, (min, max): (f32, f32)) -> Self {
+        let padding_x = PADDING.min((width - 1) / 2);
+        let padding_y = PADDING.min((height - 1) / 2);
+        Self {
+            left: padding_x,
+            right: width - 1 - padding_x,
+            top: padding_y,
+            bottom: height - 1 - padding_y,
+            min,
+            max,
+        }
+    }
+
+    /// The column of the `index`th of `count` points.
+    fn x(&self, index: usize, count: usize) -> u32 {
+        if count < 2 {
+            return self.left;
+        }
+        self.left + ((self.right - self.left) as u64 * index as u64 / (count - 1) as u64) as u32
+    }
+
+    /// The row of `value`, clamped to the plot.
+    fn y(&self, value: f32) -> u32 {
+        let fraction = ((self.max - value) / (self.max - self.min)).clamp(0.0, 1.0);
+        self.top + (fraction * (self.bottom - self.top) as f32).round() as u32
+    }
+}
+
+/// A palette image being drawn on.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    palette: Vec<[u8; 3]>,
+}
+
+impl Canvas {
+    /// The palette index of `color`, adding it when it is new.
+    fn color(&mut self, color: [u8; 3]) -> u8 {
+        match self.palette.iter().position(|entry| *entry == color) {
+            Some(index) => index as u8,
+            None => {
+                self.palette.push(color);
+                (self.palette.len() - 1) as u8
+            }
+        }
+    }
+
+    /// Fills the rectangle between two corners, both included.
+    fn fill(&mut self, x0: u32, y0: u32, x1: u32, y1: u32, color: u8) {
+        for y in y0..=y1.min(self.height - 1) {
+            for x in x0..=x1.min(self.width - 1) {
+                self.pixels[(y * self.width + x) as usize] = color;
+            }
+        }
+    }
+
+    /// Draws a line two pixels thick between two points.
+    fn line(&mut self, x0: u32, y0: u32, x1: u32, y1: u32, color: u8) {
+        let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+        loop {
+            self.fill(x as u32, y as u32, x as u32 + 1, y as u32 + 1, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
+
+fn rgb(color: i32) -> [u8; 3] {
+    [(color >> 16) as u8, (color >> 8) as u8, color as u8]
+}
+
+/// Mixes `color` over `background`, `opacity` out of 255.
+fn blend(color: [u8; 3], background: [u8; 3], opacity: u32) -> [u8; 3] {
+    let mix = |c: u8, b: u8| ((c as u32 * opacity + b as u32 * (255 - opacity)) / 255) as u8;
+    [mix(color[0], background[0]), mix(color[1], background[1]), mix(color[2], background[2])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    /// The width and height written into the IHDR chunk of a PNG.
+    fn dimensions(png: &[u8]) -> (u32, u32) {
+        let number = |at: usize| u32::from_be_bytes([png[at], png[at + 1], png[at + 2], png[at + 3]]);
+        assert_eq!(&png[12..16], b"IHDR");
+        (number(16), number(20))
+    }
+
+    #[test]
+    fn accepts_sizes_up_to_the_limit() {
+        for (width, height) in [(1, 1), (SIZE_LIMIT, 1), (1, SIZE_LIMIT), (3, 2)] {
+            let png = Chart::new(width, height).add_line([1.0, 2.0, f32::NAN, 3.0], Color::BLUE).to_png();
+            assert!(png.starts_with(PNG_SIGNATURE));
+            assert_eq!(dimensions(&png), (width, height));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "charts are 1 to 1024 pixels wide and high")]
+    fn rejects_empty_charts() {
+        Chart::new(0, 40);
+    }
+
+    #[test]
+    #[should_panic(expected = "charts are 1 to 1024 pixels wide and high")]
+    fn rejects_charts_over_the_size_limit() {
+        Chart::new(160, SIZE_LIMIT + 1);
+    }
+
+    #[test]
+    fn accepts_layers_up_to_the_limit() {
+        // Every layer gets its own color, on top of the background and axis.
+        let chart = (0..LAYER_LIMIT).fold(Chart::new(64, 16), |chart, layer| {
+            let color = layer as i32 * 0x030303 + 0x010000;
+            match layer % 3 {
+                0 => chart.add_line([layer as f32, 1.0], color),
+                1 => chart.add_bars([layer as f32], color),
+                _ => chart.add_band([(0.0, layer as f32), (1.0, 2.0)], color),
+            }
+        });
+        assert!(chart.to_png().starts_with(PNG_SIGNATURE));
+    }
+
+    #[test]
+    #[should_panic(expected = "a chart holds at most 64 series")]
+    fn rejects_layers_over_the_limit() {
+        (0..=LAYER_LIMIT).fold(Chart::new(64, 16), |chart, _| chart.add_line([1.0], Color::BLUE));
+    }
+
+    #[test]
+    fn fits_the_range_around_the_values() {
+        let chart = Chart::new(100, 100).add_line([10.0, 20.0, f32::NAN, f32::INFINITY], Color::BLUE);
+        assert_eq!(chart.range(), (9.5, 20.5));
+        // Bars always include zero.
+        assert_eq!(Chart::new(100, 100).add_bars([10.0, 30.0], Color::BLUE).range(), (-1.5, 31.5));
+        assert_eq!(Chart::new(100, 100).add_band([(-4.0, 6.0)], Color::BLUE).range(), (-4.5, 6.5));
+        assert_eq!(Chart::new(100, 100).add_line([5.0], Color::BLUE).range(), (4.0, 6.0));
+        assert_eq!(Chart::new(100, 100).add_line([f32::NAN], Color::BLUE).range(), (0.0, 1.0));
+        assert_eq!(chart.with_range(0.0, 100.0).range(), (0.0, 100.0));
+    }
+
+    #[test]
+    fn scales_values_onto_the_plot() {
+        let plot = Plot::new(100, 50, (0.0, 10.0));
+        assert_eq!((plot.left, plot.right, plot.top, plot.bottom), (4, 95, 4, 45));
+        assert_eq!(plot.y(10.0), 4);
+        assert_eq!(plot.y(0.0), 45);
+        assert_eq!(plot.y(5.0), 25);
+        // Values outside the range stick to the edges.
+        assert_eq!(plot.y(20.0), 4);
+        assert_eq!(plot.y(-20.0), 45);
+
+        assert_eq!(plot.x(0, 10), 4);
+        assert_eq!(plot.x(9, 10), 95);
+        assert_eq!(plot.x(3, 4), 95);
+        assert_eq!(plot.x(0, 1), 4);
+
+        // Tiny charts keep at least a pixel to plot on.
+        let tiny = Plot::new(1, 3, (0.0, 1.0));
+        assert_eq!((tiny.left, tiny.right, tiny.top, tiny.bottom), (0, 0, 1, 1));
+    }
+
+    #[test]
+    fn attachment_holds_the_png() {
+        let chart = Chart::sparkline([21.5, 21.8, 22.4]);
+        let attachment = chart.to_attachment("trend.png");
+        assert_eq!(attachment.filename, "trend.png");
+        assert_eq!(attachment.content_type, "image/png");
+        assert_eq!(attachment.url(), "attachment://trend.png");
+        assert_eq!(attachment.data, chart.to_png());
+        assert_eq!(dimensions(&attachment.data), (160, 40));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid attachment filename")]
+    fn attachment_rejects_invalid_filenames() {
+        Chart::sparkline([1.0]).to_attachment("charts/trend.png");
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use esp_idf_svc::http::client::EspHttpConnection;
+
+use super::{oneshot, EditMessagePacket, MessagePacket, Snowflake, WebhookBuilder, WebhookResponse, WebhookWorker, WorkerConfig};
+
+/// The default stack size of the threads requests run on, enough for an mbedTLS handshake with some headroom.
+pub(crate) const DEFAULT_STACK_SIZE: usize = 16 * 1024;
+
+/// An async front end to a [WebhookBuilder].
+///
+/// The ESP http client is blocking, so requests run on a [WebhookWorker] thread, started by the first request and shared
+/// by clones of the client, while the returned future waits for them. The worker keeps its connection open between
+/// requests. The futures do not depend on any particular runtime, so they can be awaited from an embassy executor
+/// without stalling it for the duration of the request.
+///
+/// Up to 8 requests may wait for the worker, further ones fail right away. Use [WebhookClient::with_worker_config] to
+/// change that.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{MessageBuilder, WebhookBuilder, WebhookClient};
+///
+/// async fn report() -> anyhow::Result<()> {
+///     let client = WebhookClient::new(WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"));
+///     client
+///         .send_message(MessageBuilder::new("Hello webhook!", false).build())
+///         .await?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct WebhookClient {
+    webhook: WebhookBuilder,
+    config: WorkerConfig,
+    worker: Arc<Mutex<Option<WebhookWorker>>>,
+}
+
+impl WebhookClient {
+    /// Wraps a configured webhook.
+    pub fn new(webhook: WebhookBuilder) -> Self {
+        Self {
+            webhook,
+            config: WorkerConfig::new(),
+            worker: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Sets the stack size of the thread requests run on.
+    ///
+    /// The default of 16 KiB leaves room for a TLS handshake, raise it if you see stack overflows in the worker thread.
+    pub fn with_stack_size(self, stack_size: usize) -> Self {
+        let config = self.config.clone().with_stack_size(stack_size);
+        self.with_worker_config(config)
+    }
+
+    /// Sets the queue and thread settings of the worker requests run on.
+    ///
+    /// [super::Backpressure::Block] blocks the task awaiting the request when the queue is full, which stalls an embassy
+    /// executor, so prefer [super::Backpressure::Reject] or [super::Backpressure::DropOldest].
+    pub fn with_worker_config(mut self, config: WorkerConfig) -> Self {
+        self.config = config;
+        // Clones made before keep the worker they share.
+        self.worker = Arc::new(Mutex::new(None));
+        self
+    }
+
+    /// Returns the webhook requests are sent through.
+    pub fn webhook(&self) -> &WebhookBuilder {
+        &self.webhook
+    }
+
+    /// Sends a message, resolving once discord has responded.
+    ///
+    /// See [WebhookBuilder::send_message_with_response].
+    pub async fn send_message(&self, packet: MessagePacket) -> anyhow::Result<WebhookResponse> {
+        self.submit(move |webhook, connection| webhook.send_message_on(connection, &packet))?
+            .await
+            .ok_or_else(|| anyhow!("request was dropped before it was sent"))?
+    }
+
+    /// Edits a previously sent message, resolving once discord has responded.
+    ///
+    /// See [WebhookBuilder::edit_message_with_response].
+    pub async fn edit_message(&self, packet: EditMessagePacket, id: impl Into<Snowflake>) -> anyhow::Result<WebhookResponse> {
+        let id = id.into();
+        self.submit(move |webhook, connection| webhook.edit_message_on(connection, &packet, id))?
+            .await
+            .ok_or_else(|| anyhow!("request was dropped before it was sent"))?
+    }
+
+    fn submit<T, F>(&self, request: F) -> anyhow::Result<oneshot::Receiver<anyhow::Result<T>>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WebhookBuilder, &mut Option<EspHttpConnection>) -> anyhow::Result<T> + Send + 'static,
+    {
+        let mut slot = self.worker.lock().unwrap();
+        if slot.is_none() {
+            *slot = Some(WebhookWorker::spawn(self.webhook.clone(), self.config.clone())?);
+        }
+        slot.as_ref().expect("worker was just spawned").submit(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oneshot::block_on;
+
+    #[test]
+    fn requests_resolve_through_the_shared_worker() {
+        let client = WebhookClient::new(WebhookBuilder::new("https://discord.com/api/webhooks/1/t"));
+        let clone = client.clone();
+        assert_eq!(block_on(client.submit(|_, _| Ok(1)).unwrap()).unwrap().unwrap(), 1);
+        assert!(clone.worker.lock().unwrap().is_some(), "clones share the worker");
+        assert_eq!(block_on(clone.submit(|_, _| Ok(2)).unwrap()).unwrap().unwrap(), 2);
+
+        let error = block_on(
+            client
+                .submit(|_, _| -> anyhow::Result<()> { Err(anyhow!("failed")) })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(error.unwrap_err().to_string(), "failed");
+    }
+
+    #[test]
+    fn new_worker_config_starts_a_separate_worker() {
+        let client = WebhookClient::new(WebhookBuilder::new("https://discord.com/api/webhooks/1/t"));
+        block_on(client.submit(|_, _| Ok(())).unwrap()).unwrap().unwrap();
+        let reconfigured = client.clone().with_stack_size(20 * 1024);
+        assert!(reconfigured.worker.lock().unwrap().is_none());
+        assert!(client.worker.lock().unwrap().is_some());
+    }
+}
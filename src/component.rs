@@ -0,0 +1,664 @@
+use anyhow::bail;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use super::{
+    layout::{Container, FileComponent, MediaGallery, Section, Separator, TextDisplay, Thumbnail},
+    MessageFlags, PartialEmoji, Snowflake,
+};
+
+/// The most action rows discord accepts on a message.
+const ROW_LIMIT: usize = 5;
+/// The most buttons discord accepts in a single action row.
+const BUTTON_LIMIT: usize = 5;
+/// The most components discord accepts on a Components V2 message, counting nested ones.
+const V2_COMPONENT_LIMIT: usize = 40;
+/// The most characters discord accepts across all text displays of a Components V2 message.
+const V2_TEXT_LIMIT: usize = 4000;
+
+/// How a [Button] looks and behaves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ButtonStyle {
+    Primary = 1,
+    Secondary = 2,
+    Success = 3,
+    Danger = 4,
+    /// Opens a url, the only style webhooks not owned by an application can send.
+    Link = 5,
+    /// Starts the purchase of a SKU.
+    Premium = 6,
+}
+
+impl Serialize for ButtonStyle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for ButtonStyle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match u8::deserialize(deserializer)? {
+            1 => Self::Primary,
+            2 => Self::Secondary,
+            3 => Self::Success,
+            4 => Self::Danger,
+            5 => Self::Link,
+            6 => Self::Premium,
+            style => return Err(de::Error::custom(format!("unknown button style {}", style))),
+        })
+    }
+}
+
+/// A button, placed in an [ActionRow].
+///
+/// Link buttons work on every webhook, the other styles send an interaction to the application and can only be used
+/// by webhooks an application owns.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Button {
+    pub style: ButtonStyle,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<PartialEmoji>,
+    /// Sent back in the interaction, only for non link and non premium buttons.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_id: Option<String>,
+    /// Opened when clicked, only for link buttons.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The SKU purchased, only for premium buttons.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sku_id: Option<Snowflake>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+}
+
+impl Button {
+    /// A button opening `url`, such as a dashboard or a log viewer.
+    ///
+    /// # Panics
+    /// Will panic if the provided `label` or `url` can not be converted into a [String]
+    pub fn link(label: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            style: ButtonStyle::Link,
+            label: Some(label.into()),
+            emoji: None,
+            custom_id: None,
+            url: Some(url.into()),
+            sku_id: None,
+            disabled: false,
+        }
+    }
+
+    /// A button sending an interaction with `custom_id` to the application owning the webhook.
+    ///
+    /// # Panics
+    /// Will panic if `style` is [ButtonStyle::Link] or [ButtonStyle::Premium], use [Button::link] and
+    /// [Button::premium] for those.
+    pub fn new(style: ButtonStyle, label: impl Into<String>, custom_id: impl Into<String>) -> Self {
+        assert!(
+            !matches!(style, ButtonStyle::Link | ButtonStyle::Premium),
+            "link and premium buttons have no custom id"
+        );
+        Self {
+            style,
+            label: Some(label.into()),
+            emoji: None,
+            custom_id: Some(custom_id.into()),
+            url: None,
+            sku_id: None,
+            disabled: false,
+        }
+    }
+
+    /// A button purchasing the SKU `sku_id`, discord provides the label.
+    pub fn premium(sku_id: impl Into<Snowflake>) -> Self {
+        Self {
+            style: ButtonStyle::Premium,
+            label: None,
+            emoji: None,
+            custom_id: None,
+            url: None,
+            sku_id: Some(sku_id.into()),
+            disabled: false,
+        }
+    }
+
+    /// Shows an emoji in front of the label.
+    pub fn with_emoji(mut self, emoji: PartialEmoji) -> Self {
+        self.emoji = Some(emoji);
+        self
+    }
+
+    /// Greys out the button so it can not be clicked.
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+/// What a [SelectMenu] lets the user pick from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectMenuKind {
+    /// Options provided by the menu.
+    #[default]
+    String,
+    User,
+    Role,
+    /// Users and roles.
+    Mentionable,
+    Channel,
+}
+
+impl SelectMenuKind {
+    fn component_type(self) -> u8 {
+        match self {
+            Self::String => 3,
+            Self::User => 5,
+            Self::Role => 6,
+            Self::Mentionable => 7,
+            Self::Channel => 8,
+        }
+    }
+}
+
+/// One choice of a [SelectMenuKind::String] select menu.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SelectOption {
+    pub label: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<PartialEmoji>,
+    /// Selected when the message is shown.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub default: bool,
+}
+
+impl SelectOption {
+    /// Constructs an option showing `label`, the application receives `value` when it is picked.
+    ///
+    /// # Panics
+    /// Will panic if the provided `label` or `value` can not be converted into a [String]
+    pub fn new(label: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            value: value.into(),
+            description: None,
+            emoji: None,
+            default: false,
+        }
+    }
+
+    /// Sets the text shown below the label.
+    ///
+    /// # Panics
+    /// Will panic if the provided `description` can not be converted into a [String]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Shows an emoji in front of the label.
+    pub fn with_emoji(mut self, emoji: PartialEmoji) -> Self {
+        self.emoji = Some(emoji);
+        self
+    }
+
+    /// Selects the option when the message is shown.
+    pub fn with_default(mut self, default: bool) -> Self {
+        self.default = default;
+        self
+    }
+}
+
+/// A dropdown sending an interaction to the application owning the webhook, placed alone in an [ActionRow].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SelectMenu {
+    /// Sent as the component type, so it is not a field of its own.
+    #[serde(skip)]
+    pub kind: SelectMenuKind,
+    pub custom_id: String,
+    /// The choices of a [SelectMenuKind::String] menu.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<SelectOption>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_values: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_values: Option<u8>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+}
+
+impl SelectMenu {
+    /// Constructs an empty select menu, the application receives `custom_id` in the interaction.
+    ///
+    /// # Panics
+    /// Will panic if the provided `custom_id` can not be converted into a [String]
+    pub fn new(kind: SelectMenuKind, custom_id: impl Into<String>) -> Self {
+        Self {
+            kind,
+            custom_id: custom_id.into(),
+            options: Vec::new(),
+            placeholder: None,
+            min_values: None,
+            max_values: None,
+            disabled: false,
+        }
+    }
+
+    /// Adds a choice, only used by [SelectMenuKind::String] menus.
+    pub fn add_option(mut self, option: SelectOption) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    /// Sets the text shown while nothing is selected.
+    ///
+    /// # Panics
+    /// Will panic if the provided `placeholder` can not be converted into a [String]
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.placeholder = Some(placeholder.into());
+        self
+    }
+
+    /// Sets how many choices must be picked, discord defaults to exactly one.
+    pub fn with_values(mut self, min: u8, max: u8) -> Self {
+        self.min_values = Some(min);
+        self.max_values = Some(max);
+        self
+    }
+
+    /// Greys out the menu so nothing can be picked.
+    pub fn with_disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+/// A row of up to 5 buttons or a single select menu.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{ActionRow, Button, MessageBuilder};
+///
+/// let message = MessageBuilder::new("Boiler over temperature!", false)
+///     .add_action_row(
+///         ActionRow::new()
+///             .add_button(Button::link("Open dashboard", "https://grafana.example.com/d/boiler"))
+///             .add_button(Button::link("View logs", "https://logs.example.com/boiler")),
+///     )
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ActionRow {
+    #[serde(default)]
+    pub components: Vec<Component>,
+}
+
+impl ActionRow {
+    /// Constructs an empty row.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a button to the row.
+    ///
+    /// # Panics
+    /// Will panic if the row already holds 5 buttons or a select menu.
+    pub fn add_button(mut self, button: Button) -> Self {
+        assert!(
+            self.components.len() < BUTTON_LIMIT,
+            "an action row holds at most {} buttons",
+            BUTTON_LIMIT
+        );
+        assert!(!self.has_select_menu(), "an action row with a select menu can not hold buttons");
+        self.components.push(Component::Button(button));
+        self
+    }
+
+    /// Puts a select menu in the row, which then can not hold anything else.
+    ///
+    /// # Panics
+    /// Will panic if the row is not empty.
+    pub fn with_select_menu(mut self, menu: SelectMenu) -> Self {
+        assert!(self.components.is_empty(), "a select menu must be alone in its action row");
+        self.components.push(Component::SelectMenu(menu));
+        self
+    }
+
+    fn has_select_menu(&self) -> bool {
+        self.components
+            .iter()
+            .any(|component| matches!(component, Component::SelectMenu(_)))
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.components.is_empty() {
+            bail!("action rows can not be empty");
+        }
+        if self.components.len() > BUTTON_LIMIT {
+            bail!("an action row holds at most {} buttons, got {}", BUTTON_LIMIT, self.components.len());
+        }
+        if self.has_select_menu() && self.components.len() > 1 {
+            bail!("a select menu must be alone in its action row");
+        }
+        if self
+            .components
+            .iter()
+            .any(|component| matches!(component, Component::ActionRow(_)))
+        {
+            bail!("action rows can not be nested");
+        }
+        Ok(())
+    }
+}
+
+/// An interactive or layout element of a message.
+///
+/// Action rows, buttons and select menus can be used on any message. The layout components (sections, text displays,
+/// thumbnails, media galleries, files, separators and containers) need [MessageFlags::IS_COMPONENTS_V2], which
+/// replaces the content and embeds of the message.
+///
+/// Components are told apart by their numeric `type` in JSON. Types this crate does not know, for example on a
+/// message returned by discord, are kept as [Component::Unknown] so they survive a round trip.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Component {
+    ActionRow(ActionRow),
+    Button(Button),
+    SelectMenu(SelectMenu),
+    Section(Section),
+    TextDisplay(TextDisplay),
+    Thumbnail(Thumbnail),
+    MediaGallery(MediaGallery),
+    File(FileComponent),
+    Separator(Separator),
+    Container(Container),
+    Unknown(Value),
+}
+
+impl From<ActionRow> for Component {
+    fn from(row: ActionRow) -> Self {
+        Self::ActionRow(row)
+    }
+}
+
+impl From<Button> for Component {
+    fn from(button: Button) -> Self {
+        Self::Button(button)
+    }
+}
+
+impl From<SelectMenu> for Component {
+    fn from(menu: SelectMenu) -> Self {
+        Self::SelectMenu(menu)
+    }
+}
+
+#[derive(Serialize)]
+struct Tagged<'a, T> {
+    #[serde(rename = "type")]
+    component_type: u8,
+    #[serde(flatten)]
+    component: &'a T,
+}
+
+impl Serialize for Component {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::ActionRow(row) => Tagged { component_type: 1, component: row }.serialize(serializer),
+            Self::Button(button) => Tagged { component_type: 2, component: button }.serialize(serializer),
+            Self::SelectMenu(menu) => Tagged {
+                component_type: menu.kind.component_type(),
+                component: menu,
+            }
+            .serialize(serializer),
+            Self::Section(section) => Tagged { component_type: 9, component: section }.serialize(serializer),
+            Self::TextDisplay(text) => Tagged { component_type: 10, component: text }.serialize(serializer),
+            Self::Thumbnail(thumbnail) => Tagged { component_type: 11, component: thumbnail }.serialize(serializer),
+            Self::MediaGallery(gallery) => Tagged { component_type: 12, component: gallery }.serialize(serializer),
+            Self::File(file) => Tagged { component_type: 13, component: file }.serialize(serializer),
+            Self::Separator(separator) => Tagged { component_type: 14, component: separator }.serialize(serializer),
+            Self::Container(container) => Tagged { component_type: 17, component: container }.serialize(serializer),
+            Self::Unknown(value) => value.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Component {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let component_type = value
+            .get("type")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| de::Error::missing_field("type"))?;
+        let select_menu = |value, kind| {
+            serde_json::from_value(value).map(|menu: SelectMenu| Self::SelectMenu(SelectMenu { kind, ..menu }))
+        };
+        match component_type {
+            1 => serde_json::from_value(value).map(Self::ActionRow),
+            2 => serde_json::from_value(value).map(Self::Button),
+            3 => select_menu(value, SelectMenuKind::String),
+            5 => select_menu(value, SelectMenuKind::User),
+            6 => select_menu(value, SelectMenuKind::Role),
+            7 => select_menu(value, SelectMenuKind::Mentionable),
+            8 => select_menu(value, SelectMenuKind::Channel),
+            9 => serde_json::from_value(value).map(Self::Section),
+            10 => serde_json::from_value(value).map(Self::TextDisplay),
+            11 => serde_json::from_value(value).map(Self::Thumbnail),
+            12 => serde_json::from_value(value).map(Self::MediaGallery),
+            13 => serde_json::from_value(value).map(Self::File),
+            14 => serde_json::from_value(value).map(Self::Separator),
+            17 => serde_json::from_value(value).map(Self::Container),
+            _ => Ok(Self::Unknown(value)),
+        }
+        .map_err(de::Error::custom)
+    }
+}
+
+/// Checks components against discord's limits before they are sent.
+///
+/// Without [MessageFlags::IS_COMPONENTS_V2] that is up to 5 action rows, each holding up to 5 buttons or a single select
+/// menu. With it, up to 40 components in total and 4000 characters of text, with layout components placed where
+/// discord allows them.
+pub(crate) fn validate(components: &[Component], flags: u64) -> anyhow::Result<()> {
+    if flags & MessageFlags::IS_COMPONENTS_V2 != 0 {
+        return validate_v2(components);
+    }
+    if components.len() > ROW_LIMIT {
+        bail!("a message holds at most {} action rows, got {}", ROW_LIMIT, components.len());
+    }
+    for component in components {
+        match component {
+            Component::ActionRow(row) => row.validate()?,
+            Component::Unknown(_) => {}
+            Component::Button(_) | Component::SelectMenu(_) => {
+                bail!("buttons and select menus must be placed in an action row")
+            }
+            _ => bail!("layout components need the IS_COMPONENTS_V2 flag"),
+        }
+    }
+    Ok(())
+}
+
+fn validate_v2(components: &[Component]) -> anyhow::Result<()> {
+    let mut count = 0;
+    let mut text = 0;
+    for component in components {
+        match component {
+            Component::Button(_) | Component::SelectMenu(_) => {
+                bail!("buttons and select menus must be placed in an action row")
+            }
+            Component::Thumbnail(_) => bail!("thumbnails can only be the accessory of a section"),
+            Component::Container(container) => {
+                count += 1;
+                for child in &container.components {
+                    if matches!(child, Component::Container(_)) {
+                        bail!("containers can not be nested");
+                    }
+                    if matches!(child, Component::Button(_) | Component::SelectMenu(_) | Component::Thumbnail(_)) {
+                        bail!("containers can not hold buttons, select menus or thumbnails directly");
+                    }
+                    validate_v2_child(child, &mut count, &mut text)?;
+                }
+            }
+            _ => validate_v2_child(component, &mut count, &mut text)?,
+        }
+    }
+    if count > V2_COMPONENT_LIMIT {
+        bail!("a message holds at most {} components, got {}", V2_COMPONENT_LIMIT, count);
+    }
+    if text > V2_TEXT_LIMIT {
+        bail!("a message holds at most {} characters of text, got {}", V2_TEXT_LIMIT, text);
+    }
+    Ok(())
+}
+
+/// Validates a component that is not a container, counting it and its text.
+fn validate_v2_child(component: &Component, count: &mut usize, text: &mut usize) -> anyhow::Result<()> {
+    *count += 1;
+    match component {
+        Component::ActionRow(row) => {
+            row.validate()?;
+            *count += row.components.len();
+        }
+        Component::Section(section) => {
+            if !(1..=3).contains(&section.components.len()) {
+                bail!("a section holds 1 to 3 text displays, got {}", section.components.len());
+            }
+            for line in &section.components {
+                let Component::TextDisplay(line) = line else {
+                    bail!("sections can only hold text displays");
+                };
+                *text += line.content.chars().count();
+            }
+            if !matches!(*section.accessory, Component::Thumbnail(_) | Component::Button(_)) {
+                bail!("the accessory of a section must be a thumbnail or button");
+            }
+            *count += section.components.len() + 1;
+        }
+        Component::TextDisplay(line) => *text += line.content.chars().count(),
+        Component::MediaGallery(gallery) if !(1..=10).contains(&gallery.items.len()) => {
+            bail!("a media gallery holds 1 to 10 items, got {}", gallery.items.len())
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{ContainerBuilder, TextDisplay, Thumbnail};
+
+    const V2: u64 = MessageFlags::IS_COMPONENTS_V2;
+
+    fn texts(count: usize) -> Vec<Component> {
+        (0..count).map(|i| TextDisplay::new(format!("line {}", i)).into()).collect()
+    }
+
+    #[test]
+    fn unknown_components_round_trip() {
+        let value = json!({ "type": 99, "label": "from the future", "nested": { "values": [1, 2, 3] } });
+        let component: Component = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(component, Component::Unknown(value.clone()));
+        assert_eq!(serde_json::to_value(&component).unwrap(), value);
+
+        let row: Component = serde_json::from_value(json!({ "type": 1, "components": [value.clone()] })).unwrap();
+        let Component::ActionRow(inner) = &row else {
+            panic!("expected an action row, got {:?}", row);
+        };
+        assert_eq!(inner.components, [Component::Unknown(value.clone())]);
+        assert_eq!(
+            serde_json::to_value(&row).unwrap(),
+            json!({ "type": 1, "components": [value] })
+        );
+    }
+
+    #[test]
+    fn components_without_a_type_are_rejected() {
+        assert!(serde_json::from_value::<Component>(json!({ "content": "no type" })).is_err());
+    }
+
+    #[test]
+    fn v2_allows_at_most_40_components() {
+        assert!(validate(&texts(40), V2).is_ok());
+        let error = validate(&texts(41), V2).unwrap_err().to_string();
+        assert_eq!(error, "a message holds at most 40 components, got 41");
+
+        // A container counts itself and every component inside it.
+        let container = texts(39)
+            .into_iter()
+            .fold(ContainerBuilder::new(), |builder, text| builder.add_component(text))
+            .build();
+        assert!(validate(&[container.clone().into()], V2).is_ok());
+        let mut full = container;
+        full.components.push(TextDisplay::new("one more").into());
+        assert!(validate(&[full.into()], V2).is_err());
+
+        // A section counts itself, its text displays and its accessory.
+        let section: Component = Section::new(Thumbnail::new("https://example.com/a.png"))
+            .add_text("a")
+            .add_text("b")
+            .into();
+        let mut components = texts(36);
+        components.push(section.clone());
+        assert!(validate(&components, V2).is_ok());
+        components.push(TextDisplay::new("one more").into());
+        assert!(validate(&components, V2).is_err());
+    }
+
+    #[test]
+    fn v2_allows_at_most_4000_characters() {
+        let text = |count: usize| Component::from(TextDisplay::new("é".repeat(count)));
+        assert!(validate(&[text(2000), text(2000)], V2).is_ok());
+        let error = validate(&[text(2000), text(2001)], V2).unwrap_err().to_string();
+        assert_eq!(error, "a message holds at most 4000 characters of text, got 4001");
+
+        // Text in sections and containers counts towards the same limit.
+        let section = Section::new(Thumbnail::new("https://example.com/a.png")).add_text("x".repeat(1000));
+        let container = ContainerBuilder::new().add_text("y".repeat(1000)).build();
+        assert!(validate(&[text(2000), section.clone().into(), container.clone().into()], V2).is_ok());
+        assert!(validate(&[text(2001), section.into(), container.into()], V2).is_err());
+    }
+
+    #[test]
+    fn v2_checks_where_components_are_placed() {
+        let thumbnail = Component::from(Thumbnail::new("https://example.com/a.png"));
+        assert!(validate(std::slice::from_ref(&thumbnail), V2).is_err());
+        assert!(validate(&[Button::link("Docs", "https://example.com").into()], V2).is_err());
+
+        let nested = ContainerBuilder::new()
+            .add_component(ContainerBuilder::new().build())
+            .build();
+        assert_eq!(
+            validate(&[nested.into()], V2).unwrap_err().to_string(),
+            "containers can not be nested"
+        );
+        let loose = ContainerBuilder::new().add_component(thumbnail).build();
+        assert!(validate(&[loose.into()], V2).is_err());
+
+        let empty = Section::new(Thumbnail::new("https://example.com/a.png"));
+        assert!(validate(&[empty.into()], V2).is_err());
+        let bad_accessory = Section::new(TextDisplay::new("not an accessory")).add_text("a");
+        assert!(validate(&[bad_accessory.into()], V2).is_err());
+        let empty_gallery = Component::from(crate::MediaGallery::new());
+        assert!(validate(&[empty_gallery], V2).is_err());
+    }
+
+    #[test]
+    fn layout_components_need_the_v2_flag() {
+        assert_eq!(
+            validate(&texts(1), 0).unwrap_err().to_string(),
+            "layout components need the IS_COMPONENTS_V2 flag"
+        );
+        let row = ActionRow::new().add_button(Button::link("Docs", "https://example.com"));
+        assert!(validate(&[row.into()], 0).is_ok());
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    ffi::{CStr, CString},
+    sync::Mutex,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail};
+
+use esp_idf_svc::{http::client::Configuration, tls::X509};
+
+use super::proxy::ProxyConfig;
+
+/// Every custom CA handed to a connection so far. ESP-IDF keeps a pointer to the certificate for as long as the
+/// connection lives, so each distinct PEM is leaked once and shared by all connections using it.
+static CA_CERTIFICATES: Mutex<Vec<&'static CStr>> = Mutex::new(Vec::new());
+
+/// How many distinct CA certificates may be leaked into [CA_CERTIFICATES] before further ones are refused.
+const MAX_CA_CERTIFICATES: usize = 4;
+
+/// Connection settings used for every request a webhook makes.
+///
+/// The default matches what discord needs out of the box: the certificate bundle shipped with ESP-IDF, the global CA
+/// store, and ESP-IDF's default timeouts and buffer sizes.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use diswh_esp::{WebhookBuilder, WebhookConfig};
+///
+/// let webhook = WebhookBuilder::new("https://discord.com/api/webhooks/1234/token")
+///     .with_config(
+///         WebhookConfig::new()
+///             .with_connect_timeout(Duration::from_secs(5))
+///             .with_read_timeout(Duration::from_secs(10))
+///             .with_ca_pem("-----BEGIN CERTIFICATE-----\n...\n-----END CERTIFICATE-----\n")
+///     );
+/// ```
+#[derive(Clone, Debug)]
+pub struct WebhookConfig {
+    pub connect_timeout: Option<Duration>,
+    pub read_timeout: Option<Duration>,
+    pub rx_buffer_size: Option<usize>,
+    pub tx_buffer_size: Option<usize>,
+    pub use_global_ca_store: bool,
+    pub use_crt_bundle: bool,
+    pub ca_pem: Option<String>,
+    pub pinned_certificate: Option<[u8; 32]>,
+    pub proxy: Option<ProxyConfig>,
+}
+
+impl WebhookConfig {
+    /// Constructs the default config.
+    pub fn new() -> Self {
+        Self {
+            connect_timeout: None,
+            read_timeout: None,
+            rx_buffer_size: None,
+            tx_buffer_size: None,
+            use_global_ca_store: true,
+            use_crt_bundle: true,
+            ca_pem: None,
+            pinned_certificate: None,
+            proxy: None,
+        }
+    }
+
+    /// Sets both the connect and the read timeout.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_connect_timeout(timeout).with_read_timeout(timeout)
+    }
+
+    /// Sets how long connecting to the server, including the TLS handshake, may take.
+    ///
+    /// The ESP http client has a single timeout for connecting and reading, so a connect timeout that differs from the
+    /// read timeout sends requests over a connection opened by hand, which is not kept open between requests.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how long each read or write may wait on the server, for example while discord prepares its response.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the size of the receive buffer, raise it if discord's response headers do not fit.
+    pub fn with_rx_buffer_size(mut self, size: usize) -> Self {
+        self.rx_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the size of the transmit buffer.
+    pub fn with_tx_buffer_size(mut self, size: usize) -> Self {
+        self.tx_buffer_size = Some(size);
+        self
+    }
+
+    /// Sets whether certificates in the global CA store are trusted.
+    pub fn with_global_ca_store(mut self, use_global_ca_store: bool) -> Self {
+        self.use_global_ca_store = use_global_ca_store;
+        self
+    }
+
+    /// Sets whether the certificate bundle shipped with ESP-IDF is trusted.
+    pub fn with_crt_bundle(mut self, use_crt_bundle: bool) -> Self {
+        self.use_crt_bundle = use_crt_bundle;
+        self
+    }
+
+    /// Trusts only the given CA certificate, such as the one of a TLS intercepting proxy.
+    ///
+    /// The certificate is set on each connection the webhook makes, so webhooks with different CAs do not affect each
+    /// other. ESP-TLS checks a connection against a single trust source, so the certificate bundle and the global CA
+    /// store are not consulted while a CA is set. The server's chain has to lead up to this certificate, which pins the
+    /// webhook to the CA, not to the server's own certificate, see [WebhookConfig::with_pinned_certificate] for that.
+    ///
+    /// Each distinct PEM stays in memory until the chip restarts, since ESP-IDF keeps pointing at it. Requests fail once
+    /// more than 4 distinct CAs have been used.
+    ///
+    /// # Panics
+    /// Will panic if the provided `pem` can not be converted into a [String]
+    pub fn with_ca_pem(mut self, pem: impl Into<String>) -> Self {
+        self.ca_pem = Some(pem.into());
+        self
+    }
+
+    /// Only accepts servers whose certificate has the given SHA-256 fingerprint, on top of the usual chain checks.
+    ///
+    /// The fingerprint is the hex digest of the DER encoded certificate, as printed by
+    /// `openssl x509 -noout -fingerprint -sha256`. Colons and spaces between the bytes are ignored. The ESP http client
+    /// does not expose the server's certificate, so pinned requests go over a connection opened by hand, which is not
+    /// kept open between requests. It needs `CONFIG_MBEDTLS_SSL_KEEP_PEER_CERTIFICATE`, which ESP-IDF enables by default.
+    ///
+    /// # Panics
+    /// Will panic if `fingerprint` is not 32 bytes of hex.
+    pub fn with_pinned_certificate(mut self, fingerprint: &str) -> Self {
+        let fingerprint =
+            parse_fingerprint(fingerprint).expect("pinned certificate must be a SHA-256 fingerprint in hex");
+        self.pinned_certificate = Some(fingerprint);
+        self
+    }
+
+    /// Routes every request through an http proxy.
+    ///
+    /// The timeout and TLS settings above still apply to the connection to discord made through the proxy.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Returns true when requests can not go through the ESP http client and need a connection opened by hand.
+    pub(crate) fn needs_raw_connection(&self) -> bool {
+        let split_timeouts = self.connect_timeout.is_some() && self.connect_timeout != self.read_timeout;
+        self.proxy.is_some() || self.pinned_certificate.is_some() || split_timeouts
+    }
+
+    /// Builds the esp http client configuration.
+    pub(crate) fn http_configuration(&self) -> anyhow::Result<Configuration> {
+        let ca_certificate = self.ca_certificate()?;
+        Ok(Configuration {
+            // Connections with a separate connect timeout are opened by hand, so only one of them is left here.
+            timeout: self.read_timeout.or(self.connect_timeout),
+            buffer_size: self.rx_buffer_size,
+            buffer_size_tx: self.tx_buffer_size,
+            // ESP-TLS prefers the bundle and the global store over the certificate, turn them off so it is used.
+            use_global_ca_store: self.use_global_ca_store && ca_certificate.is_none(),
+            crt_bundle_attach: if self.use_crt_bundle && ca_certificate.is_none() {
+                Some(esp_idf_svc::sys::esp_crt_bundle_attach)
+            } else {
+                None
+            },
+            server_certificate: ca_certificate,
+            ..Default::default()
+        })
+    }
+
+    /// Returns the custom CA as ESP-IDF takes it, if there is one.
+    pub(crate) fn ca_certificate(&self) -> anyhow::Result<Option<X509<'static>>> {
+        let Some(pem) = &self.ca_pem else {
+            return Ok(None);
+        };
+        // mbedtls wants PEM data to be nul terminated, with the terminator counted in the length.
+        let pem = CString::new(pem.as_str()).map_err(|_| anyhow!("CA certificate contains a nul byte"))?;
+        let mut certificates = CA_CERTIFICATES.lock().unwrap();
+        let pem = match certificates.iter().find(|certificate| **certificate == pem.as_c_str()) {
+            Some(certificate) => *certificate,
+            None if certificates.len() >= MAX_CA_CERTIFICATES => {
+                bail!("at most {} distinct CA certificates can be used", MAX_CA_CERTIFICATES)
+            }
+            None => {
+                let certificate: &'static CStr = Box::leak(pem.into_boxed_c_str());
+                certificates.push(certificate);
+                certificate
+            }
+        };
+        Ok(Some(X509::pem(pem)))
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses a SHA-256 fingerprint written as hex, with or without separators between the bytes.
+fn parse_fingerprint(fingerprint: &str) -> Option<[u8; 32]> {
+    let digits: Vec<u8> = fingerprint
+        .bytes()
+        .filter(|byte| !matches!(byte, b':' | b' '))
+        .collect();
+    if digits.len() != 64 || !digits.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+    let mut parsed = [0; 32];
+    for (byte, pair) in parsed.iter_mut().zip(digits.chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_fingerprints_with_and_without_separators() {
+        let plain = "00112233445566778899AABBCCDDEEFF00112233445566778899aabbccddeeff";
+        let expected = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff, 0x00, 0x11,
+            0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+        ];
+        assert_eq!(parse_fingerprint(plain), Some(expected));
+        let separated = plain
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| std::str::from_utf8(pair).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(parse_fingerprint(&separated.join(":")), Some(expected));
+        assert_eq!(parse_fingerprint(&separated.join(" ")), Some(expected));
+
+        assert_eq!(parse_fingerprint(&plain[2..]), None);
+        assert_eq!(parse_fingerprint(&plain.replace('A', "G")), None);
+        assert_eq!(
+            parse_fingerprint("+1112233445566778899AABBCCDDEEFF00112233445566778899aabbccddeeff"),
+            None
+        );
+    }
+
+    #[test]
+    fn split_timeouts_and_pinning_need_a_raw_connection() {
+        let config = WebhookConfig::new();
+        assert!(!config.needs_raw_connection());
+        assert!(!config
+            .clone()
+            .with_timeout(Duration::from_secs(5))
+            .needs_raw_connection());
+        assert!(!config
+            .clone()
+            .with_read_timeout(Duration::from_secs(5))
+            .needs_raw_connection());
+        let split = config
+            .clone()
+            .with_connect_timeout(Duration::from_secs(2))
+            .with_read_timeout(Duration::from_secs(5));
+        assert!(split.needs_raw_connection());
+        assert_eq!(
+            split.http_configuration().unwrap().timeout,
+            Some(Duration::from_secs(5))
+        );
+        let pinned = config.with_pinned_certificate(&"ab".repeat(32));
+        assert_eq!(pinned.pinned_certificate, Some([0xab; 32]));
+        assert!(pinned.needs_raw_connection());
+    }
+
+    #[test]
+    fn ca_certificates_are_shared_and_bounded() {
+        let config = |index: usize| WebhookConfig::new().with_ca_pem(format!("certificate {}", index));
+        for index in 0..MAX_CA_CERTIFICATES {
+            config(index).ca_certificate().unwrap();
+            config(index).ca_certificate().unwrap();
+        }
+        assert_eq!(CA_CERTIFICATES.lock().unwrap().len(), MAX_CA_CERTIFICATES);
+        let error = config(MAX_CA_CERTIFICATES).ca_certificate().unwrap_err();
+        assert_eq!(error.to_string(), "at most 4 distinct CA certificates can be used");
+        assert!(WebhookConfig::new().with_ca_pem("nul\0").ca_certificate().is_err());
+    }
+}
@@ -0,0 +1,175 @@
+use super::{
+    component::{ActionRow, Component},
+    layout::*,
+};
+
+/// A helper struct to make it easy to construct a [super::Container] using a chain style, the Components V2 counterpart
+/// of [super::EmbedBuilder].
+///
+/// Add the container to a message with [super::MessageBuilder::add_component], which also marks the message as
+/// Components V2. Such messages can not have content or embeds.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{Color, ContainerBuilder, MessageBuilder, Thumbnail};
+///
+/// let container = ContainerBuilder::new()
+///     .with_accent_color(Color::GREEN)
+///     .add_text("## Greenhouse")
+///     .add_separator(true)
+///     .add_section(Thumbnail::new("https://example.com/plant.png"), ["Temperature: 21.4 °C", "Humidity: 64 %"])
+///     .build();
+/// let packet = MessageBuilder::new("", false).add_component(container).build();
+///
+/// assert_eq!(
+///     serde_json::to_value(&packet).unwrap(),
+///     serde_json::json!({
+///         "tts": false,
+///         "flags": 32768,
+///         "components": [{
+///             "type": 17,
+///             "accent_color": 5763719,
+///             "components": [
+///                 { "type": 10, "content": "## Greenhouse" },
+///                 { "type": 14, "divider": true, "spacing": 1 },
+///                 {
+///                     "type": 9,
+///                     "components": [
+///                         { "type": 10, "content": "Temperature: 21.4 °C" },
+///                         { "type": 10, "content": "Humidity: 64 %" }
+///                     ],
+///                     "accessory": { "type": 11, "media": { "url": "https://example.com/plant.png" } }
+///                 }
+///             ]
+///         }]
+///     })
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct ContainerBuilder {
+    container: Container,
+}
+
+impl ContainerBuilder {
+    /// Initialize a blank container builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the color of the bar on the left of the container.
+    ///
+    /// Use the provided [super::Color] struct, or [super::Color::from_hex], to get a color.
+    pub fn with_accent_color(mut self, color: i32) -> Self {
+        self.container.accent_color = Some(color);
+        self
+    }
+
+    /// Hides the whole container until it is clicked.
+    pub fn with_spoiler(mut self, spoiler: bool) -> Self {
+        self.container.spoiler = spoiler;
+        self
+    }
+
+    /// Adds markdown text to the container.
+    ///
+    /// # Panics
+    /// Will panic when the provided `content` is not able to be converted into a [String]
+    pub fn add_text(self, content: impl Into<String>) -> Self {
+        self.add_component(TextDisplay::new(content))
+    }
+
+    /// Adds up to 3 lines of markdown text with `accessory` next to them, a [Thumbnail] or [super::Button].
+    ///
+    /// # Panics
+    /// Will panic when more than 3 lines are provided.
+    pub fn add_section<T: Into<String>>(self, accessory: impl Into<Component>, lines: impl IntoIterator<Item = T>) -> Self {
+        let section = lines
+            .into_iter()
+            .fold(Section::new(accessory), |section, line| section.add_text(line));
+        self.add_component(section)
+    }
+
+    /// Adds a small gap between components, drawing a line when `divider` is set.
+    pub fn add_separator(self, divider: bool) -> Self {
+        self.add_component(Separator::new(divider))
+    }
+
+    /// Adds a grid of images or videos.
+    pub fn add_media_gallery(self, gallery: MediaGallery) -> Self {
+        self.add_component(gallery)
+    }
+
+    /// Adds an attached file, `url` must point at an attachment, as `attachment://log.txt`.
+    ///
+    /// # Panics
+    /// Will panic when the provided `url` is not able to be converted into a [String]
+    pub fn add_file(self, url: impl Into<String>) -> Self {
+        self.add_component(FileComponent::new(url))
+    }
+
+    /// Adds a row of buttons or a select menu.
+    pub fn add_action_row(self, row: ActionRow) -> Self {
+        self.add_component(row)
+    }
+
+    /// Adds any component that may be placed in a container.
+    pub fn add_component(mut self, component: impl Into<Component>) -> Self {
+        self.container.components.push(component.into());
+        self
+    }
+
+    /// Decompose the inner container into a owned object
+    ///
+    /// # Warning
+    /// This action is destructive, the container builder will no longer exist after performing this action.
+    pub fn build(self) -> Container {
+        self.container
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Button, Color};
+
+    #[test]
+    fn container_serializes_as_type_17() {
+        let container = ContainerBuilder::new()
+            .with_accent_color(Color::RED)
+            .with_spoiler(true)
+            .add_text("## Camera")
+            .add_separator(false)
+            .add_media_gallery(MediaGallery::new().add_url("attachment://cam.jpg"))
+            .add_file("attachment://log.txt")
+            .add_action_row(ActionRow::new().add_button(Button::link("Dashboard", "https://example.com")))
+            .build();
+        assert_eq!(
+            serde_json::to_value(Component::from(container)).unwrap(),
+            json!({
+                "type": 17,
+                "accent_color": Color::RED,
+                "spoiler": true,
+                "components": [
+                    { "type": 10, "content": "## Camera" },
+                    { "type": 14, "divider": false, "spacing": 1 },
+                    { "type": 12, "items": [{ "media": { "url": "attachment://cam.jpg" } }] },
+                    { "type": 13, "file": { "url": "attachment://log.txt" } },
+                    {
+                        "type": 1,
+                        "components": [{ "type": 2, "style": 5, "label": "Dashboard", "url": "https://example.com" }]
+                    }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn empty_container_leaves_out_defaults() {
+        assert_eq!(
+            serde_json::to_value(Component::from(ContainerBuilder::new().build())).unwrap(),
+            json!({ "type": 17, "components": [] })
+        );
+    }
+}
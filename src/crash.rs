@@ -0,0 +1,299 @@
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    panic::{self, PanicHookInfo},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::bail;
+use log::error;
+
+use super::{
+    client::DEFAULT_STACK_SIZE,
+    device::{self, ResetReason},
+    markdown,
+    util::{format_duration, truncate},
+    Color, EmbedBuilder, MessageBuilder, MessagePacket, Storage, WebhookBuilder,
+};
+
+type SharedStorage = Arc<Mutex<Box<dyn Storage + Send>>>;
+
+/// The name of the thread that sends panic reports.
+const SENDER_THREAD: &str = "diswh-crash";
+/// How long the panic hook waits for the report to be sent before letting the panic go on.
+const SEND_TIMEOUT: Duration = Duration::from_secs(20);
+/// Stored in place of a report once the panic hook sent it, so the next boot knows the panic was reported.
+const SENT_MARKER: &[u8] = b"sent";
+
+/// Hands the report of a panic from the panicking thread to the sender thread.
+#[derive(Default)]
+struct Outbox {
+    delivery: Mutex<Delivery>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct Delivery {
+    packet: Option<MessagePacket>,
+    saved: bool,
+    done: bool,
+}
+
+/// Reports panics and abnormal resets to discord.
+///
+/// Once installed, a panic is formatted into an embed with the message, location, device, uptime and backtrace
+/// (where the platform can capture one). The report is sent by a thread started by [CrashReporter::install], as the
+/// panicking thread may be short on stack, the hook waits up to 20 seconds for it. With storage set, the report is
+/// saved before sending is attempted, so a report that could not be sent goes out on the next boot with
+/// [CrashReporter::send_pending].
+///
+/// Storage also keeps the reset caused by a reported panic from being reported a second time by
+/// [CrashReporter::report_reset]. Without it, both the panic and the reset it caused are reported.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{CrashReporter, WebhookBuilder};
+/// use esp_idf_svc::nvs::{EspDefaultNvs, EspDefaultNvsPartition};
+///
+/// let nvs = EspDefaultNvs::new(EspDefaultNvsPartition::take()?, "crash", true)?;
+/// let reporter = CrashReporter::new(WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"), "pump-controller-3")
+///     .with_storage(nvs, "report");
+///
+/// // Once the network is up
+/// reporter.send_pending()?;
+/// reporter.report_reset()?;
+/// reporter.install()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct CrashReporter {
+    webhook: WebhookBuilder,
+    device: String,
+    storage: Option<(SharedStorage, String)>,
+    outbox: Arc<Outbox>,
+    panic_reported: Arc<AtomicBool>,
+}
+
+impl CrashReporter {
+    /// Constructs a reporter, `device` is shown in every report to tell devices apart.
+    ///
+    /// # Panics
+    /// Will panic if the provided `device` can not be converted into a [String]
+    pub fn new(webhook: WebhookBuilder, device: impl Into<String>) -> Self {
+        Self {
+            webhook,
+            device: device.into(),
+            storage: None,
+            outbox: Arc::new(Outbox::default()),
+            panic_reported: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Saves reports in `storage` under `key` until they were sent.
+    ///
+    /// # Panics
+    /// Will panic if the provided `key` can not be converted into a [String]
+    pub fn with_storage(mut self, storage: impl Storage + Send + 'static, key: impl Into<String>) -> Self {
+        self.storage = Some((Arc::new(Mutex::new(Box::new(storage))), key.into()));
+        self
+    }
+
+    /// Starts the thread reports are sent from and installs the panic hook, the previously installed hook still runs
+    /// first.
+    pub fn install(&self) -> anyhow::Result<()> {
+        let sender = self.clone();
+        thread::Builder::new()
+            .name(SENDER_THREAD.into())
+            .stack_size(DEFAULT_STACK_SIZE)
+            .spawn(move || sender.send_reports())?;
+        let reporter = self.clone();
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+            reporter.report_panic(info);
+        }));
+        Ok(())
+    }
+
+    /// Sends a report saved by a previous boot, if there is one.
+    ///
+    /// Returns whether a report was sent.
+    pub fn send_pending(&self) -> anyhow::Result<bool> {
+        let Some((storage, key)) = &self.storage else {
+            return Ok(false);
+        };
+        let mut storage = storage.lock().unwrap();
+        let Some(saved) = storage.load(key)? else {
+            return Ok(false);
+        };
+        let pending = saved != SENT_MARKER;
+        if pending {
+            let packet: MessagePacket = serde_json::from_slice(&saved)?;
+            let response = self.webhook.send_message_with_response(&packet)?;
+            if !response.is_success() {
+                bail!("sending crash report failed with status {}", response.status);
+            }
+        }
+        storage.remove(key)?;
+        self.panic_reported.store(true, Ordering::Relaxed);
+        Ok(pending)
+    }
+
+    /// Sends a report when the last reset was abnormal, such as a panic, watchdog or brownout.
+    ///
+    /// A reset caused by a panic is skipped when the panic itself was reported, or is waiting in storage to be sent by
+    /// [CrashReporter::send_pending]. Returns whether a report was sent.
+    pub fn report_reset(&self) -> anyhow::Result<bool> {
+        let reason = ResetReason::current();
+        if !reason.is_abnormal() || (reason == ResetReason::Panic && self.panic_reported()?) {
+            return Ok(false);
+        }
+        let embed = EmbedBuilder::new()
+            .with_title(format!("{} restarted", self.device))
+            .with_description(format!("The last reset was caused by a {}.", reason))
+            .with_color(Color::ORANGE)
+            .build();
+        let response = self
+            .webhook
+            .send_message_with_response(&MessageBuilder::new("", false).add_embed(embed).build())?;
+        if !response.is_success() {
+            bail!("sending reset report failed with status {}", response.status);
+        }
+        Ok(true)
+    }
+
+    /// Returns whether the panic behind the last reset was reported, or is saved to be reported.
+    fn panic_reported(&self) -> anyhow::Result<bool> {
+        if self.panic_reported.load(Ordering::Relaxed) {
+            return Ok(true);
+        }
+        match &self.storage {
+            Some((storage, key)) => Ok(storage.lock().unwrap().load(key)?.is_some()),
+            None => Ok(false),
+        }
+    }
+
+    fn report_panic(&self, info: &PanicHookInfo) {
+        let packet = self.panic_packet(info);
+        let saved = match (&self.storage, serde_json::to_vec(&packet)) {
+            (Some((storage, key)), Ok(json)) => {
+                // The panic may have happened while the storage was locked, in which case we can only try to send.
+                match storage.try_lock() {
+                    Ok(mut storage) => storage.store(key, &json).is_ok(),
+                    Err(_) => false,
+                }
+            }
+            _ => false,
+        };
+        if thread::current().name() == Some(SENDER_THREAD) {
+            // Nobody is left to send it, the saved report goes out on the next boot.
+            return;
+        }
+        let Ok(mut delivery) = self.outbox.delivery.lock() else {
+            return;
+        };
+        *delivery = Delivery {
+            packet: Some(packet),
+            saved,
+            done: false,
+        };
+        self.outbox.changed.notify_all();
+        let _ = self
+            .outbox
+            .changed
+            .wait_timeout_while(delivery, SEND_TIMEOUT, |delivery| !delivery.done);
+    }
+
+    /// Runs on the sender thread, sending the reports the panic hook hands over.
+    fn send_reports(&self) {
+        loop {
+            let (packet, saved) = {
+                let mut delivery = self.outbox.delivery.lock().unwrap();
+                loop {
+                    if let Some(packet) = delivery.packet.take() {
+                        break (packet, delivery.saved);
+                    }
+                    delivery = self.outbox.changed.wait(delivery).unwrap();
+                }
+            };
+            match self.webhook.send_message_with_response(&packet) {
+                Ok(response) if response.is_success() => {
+                    if let (true, Some((storage, key))) = (saved, &self.storage) {
+                        if let Ok(mut storage) = storage.try_lock() {
+                            let _ = storage.store(key, SENT_MARKER);
+                        }
+                    }
+                }
+                Ok(response) => error!("Sending crash report failed with status {}", response.status),
+                Err(e) => error!("Failed to send crash report: {}", e),
+            }
+            self.outbox.delivery.lock().unwrap().done = true;
+            self.outbox.changed.notify_all();
+        }
+    }
+
+    fn panic_packet(&self, info: &PanicHookInfo) -> MessagePacket {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("Box<dyn Any>");
+        let location = info
+            .location()
+            .map(|location| location.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let mut embed = EmbedBuilder::new()
+            .with_title(format!("{} panicked", self.device))
+            .with_description(markdown::code_block("", truncate(message, 4000)))
+            .with_color(Color::RED)
+            .add_field("Location", location, false)
+            .add_field("Thread", thread::current().name().unwrap_or("unnamed").to_string(), true)
+            .add_field("Uptime", format_duration(device::uptime()), true);
+        let backtrace = Backtrace::force_capture();
+        if backtrace.status() == BacktraceStatus::Captured {
+            embed = embed.add_field(
+                "Backtrace",
+                markdown::code_block("", truncate(&backtrace.to_string(), 1000)),
+                false,
+            );
+        }
+        MessageBuilder::new("", false).add_embed(embed.build()).build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStorage;
+
+    fn reporter(saved: Option<&[u8]>) -> CrashReporter {
+        let mut storage = MemoryStorage::new();
+        if let Some(saved) = saved {
+            storage.store("report", saved).unwrap();
+        }
+        CrashReporter::new(WebhookBuilder::new("https://discord.com/api/webhooks/1/t"), "test").with_storage(storage, "report")
+    }
+
+    #[test]
+    fn sent_marker_is_cleared_without_sending() {
+        let reporter = reporter(Some(SENT_MARKER));
+        assert!(reporter.panic_reported().unwrap());
+        assert!(!reporter.send_pending().unwrap());
+        // The panic still counts as reported for the rest of this boot.
+        assert!(reporter.panic_reported().unwrap());
+        let (storage, key) = reporter.storage.as_ref().unwrap();
+        assert_eq!(storage.lock().unwrap().load(key).unwrap(), None);
+    }
+
+    #[test]
+    fn saved_report_covers_the_panic_reset() {
+        assert!(reporter(Some(b"{}")).panic_reported().unwrap());
+        assert!(!reporter(None).panic_reported().unwrap());
+    }
+}
@@ -0,0 +1,93 @@
+use std::{fmt, time::Duration};
+
+use esp_idf_svc::sys;
+
+/// Time since the chip booted.
+pub fn uptime() -> Duration {
+    let micros = unsafe { sys::esp_timer_get_time() };
+    Duration::from_micros(micros.max(0) as u64)
+}
+
+/// Bytes of heap currently free.
+pub fn free_heap() -> u32 {
+    unsafe { sys::esp_get_free_heap_size() }
+}
+
+/// The fewest bytes of heap that were free at any point since boot.
+pub fn min_free_heap() -> u32 {
+    unsafe { sys::esp_get_minimum_free_heap_size() }
+}
+
+/// Bytes of stack the current thread has never used since it started, to tune the stack size of threads.
+pub fn stack_headroom() -> usize {
+    unsafe { sys::uxTaskGetStackHighWaterMark(std::ptr::null_mut()) as usize }
+}
+
+/// Signal strength of the access point the station is connected to in dBm, or [None] when not connected.
+pub fn rssi() -> Option<i8> {
+    let mut record = sys::wifi_ap_record_t::default();
+    sys::esp!(unsafe { sys::esp_wifi_sta_get_ap_info(&mut record) }).ok()?;
+    Some(record.rssi)
+}
+
+/// Why the chip last reset, as reported by ESP-IDF.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResetReason {
+    PowerOn,
+    External,
+    Software,
+    Panic,
+    InterruptWatchdog,
+    TaskWatchdog,
+    OtherWatchdog,
+    DeepSleep,
+    Brownout,
+    Sdio,
+    Unknown,
+}
+
+impl ResetReason {
+    /// Reads the reason of the last reset.
+    pub fn current() -> Self {
+        #[allow(non_upper_case_globals)]
+        match unsafe { sys::esp_reset_reason() } {
+            sys::esp_reset_reason_t_ESP_RST_POWERON => Self::PowerOn,
+            sys::esp_reset_reason_t_ESP_RST_EXT => Self::External,
+            sys::esp_reset_reason_t_ESP_RST_SW => Self::Software,
+            sys::esp_reset_reason_t_ESP_RST_PANIC => Self::Panic,
+            sys::esp_reset_reason_t_ESP_RST_INT_WDT => Self::InterruptWatchdog,
+            sys::esp_reset_reason_t_ESP_RST_TASK_WDT => Self::TaskWatchdog,
+            sys::esp_reset_reason_t_ESP_RST_WDT => Self::OtherWatchdog,
+            sys::esp_reset_reason_t_ESP_RST_DEEPSLEEP => Self::DeepSleep,
+            sys::esp_reset_reason_t_ESP_RST_BROWNOUT => Self::Brownout,
+            sys::esp_reset_reason_t_ESP_RST_SDIO => Self::Sdio,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Returns true for resets that point at a problem, such as panics, watchdogs and brownouts.
+    pub fn is_abnormal(self) -> bool {
+        matches!(
+            self,
+            Self::Panic | Self::InterruptWatchdog | Self::TaskWatchdog | Self::OtherWatchdog | Self::Brownout
+        )
+    }
+}
+
+impl fmt::Display for ResetReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PowerOn => "power on",
+            Self::External => "external reset",
+            Self::Software => "software reset",
+            Self::Panic => "panic",
+            Self::InterruptWatchdog => "interrupt watchdog",
+            Self::TaskWatchdog => "task watchdog",
+            Self::OtherWatchdog => "watchdog",
+            Self::DeepSleep => "deep sleep wake up",
+            Self::Brownout => "brownout",
+            Self::Sdio => "SDIO reset",
+            Self::Unknown => "unknown",
+        })
+    }
+}
@@ -1,31 +1,77 @@
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
 
-use serde::Serialize;
-use serde_json::{json, Value};
-
-use super::embed::Embed;
+use super::{component::Component, embed::Embed, message};
 
 /// A edit packet, sorta useless given you have to figure out he message id. But could be usefull if you are editing say a Rules embed.
-#[derive(Clone, Serialize)]
+///
+/// Like [super::MessagePacket] this can be deserialized, unknown fields are ignored.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{EditMessageBuilder, EditMessagePacket};
+///
+/// let parsed: EditMessagePacket = serde_json::from_str(r#"{"content": "Rules", "id": "123"}"#).unwrap();
+/// assert_eq!(parsed, EditMessageBuilder::new("Rules").build());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EditMessagePacket {
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub content: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub embeds: Vec<Embed>,
+    /// `None` leaves the components of the message as they are, an empty list removes them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<Component>>,
+    /// See [super::MessageFlags], only [super::MessageFlags::SUPPRESS_EMBEDS] and
+    /// [super::MessageFlags::IS_COMPONENTS_V2] can be changed by an edit.
+    #[serde(skip_serializing_if = "message::is_zero")]
+    pub flags: u64,
 }
 
 impl EditMessagePacket {
-    pub(crate) fn serialize_packet(&self) -> String {
-        let mut j = json!({});
-        if !self.content.is_empty() {
-            j["content"] = json!(self.content);
-        }
-        if !self.embeds.is_empty() {
-            let mut embeds_json = VecDeque::new();
-            for embed in &self.embeds {
-                let e: Value = serde_json::to_value(embed).unwrap();
-                embeds_json.push_back(e);
-            }
-            j["embeds"] = json!(embeds_json);
-        }
-        j.to_string()
+    /// Checks the components against discord's limits, and that Components V2 messages have no content or embeds.
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        message::validate(&self.content, &self.embeds, self.components.as_deref().unwrap_or_default(), self.flags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EditMessageBuilder, EmbedBuilder};
+
+    #[test]
+    fn round_trips_through_json() {
+        let packet = EditMessagePacket {
+            content: "Rules".into(),
+            embeds: vec![EmbedBuilder::new().with_description("Be nice").build()],
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&packet).unwrap();
+        assert_eq!(serde_json::from_str::<EditMessagePacket>(&json).unwrap(), packet);
+    }
+
+    #[test]
+    fn keeps_or_clears_components() {
+        let keep = EditMessageBuilder::new("Rules").build();
+        assert_eq!(serde_json::to_string(&keep).unwrap(), r#"{"content":"Rules"}"#);
+        let clear = EditMessageBuilder::new("Rules").clear_components().build();
+        assert_eq!(serde_json::to_string(&clear).unwrap(), r#"{"content":"Rules","components":[]}"#);
+
+        let parsed: EditMessagePacket = serde_json::from_str(r#"{"components": []}"#).unwrap();
+        assert_eq!(parsed.components, Some(Vec::new()));
+        let parsed: EditMessagePacket = serde_json::from_str("{}").unwrap();
+        assert_eq!(parsed.components, None);
+    }
+
+    #[test]
+    fn reads_a_sent_message() {
+        let packet: EditMessagePacket = serde_json::from_str(
+            r#"{"id": "1", "type": 0, "content": "Rules", "embeds": [{"type": "rich", "description": "Be nice"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(packet.content, "Rules");
+        assert_eq!(packet.embeds[0].description.as_deref(), Some("Be nice"));
     }
 }
@@ -1,4 +1,9 @@
-use super::{edit::EditMessagePacket, embed::Embed};
+use super::{
+    component::{ActionRow, Component},
+    flags::MessageFlags,
+    edit::EditMessagePacket,
+    embed::Embed,
+};
 
 #[derive(Clone)]
 pub struct EditMessageBuilder {
@@ -14,6 +19,8 @@ impl EditMessageBuilder {
             message: EditMessagePacket {
                 content: content.into(),
                 embeds: Vec::new(),
+                components: None,
+                flags: 0,
             },
         }
     }
@@ -29,6 +36,37 @@ impl EditMessageBuilder {
         self
     }
 
+    /// Adds a row of buttons or a select menu to the edited message.
+    ///
+    /// Webhooks not owned by an application can only send [super::Button::link] buttons.
+    ///
+    /// # Panics
+    /// Will panic if the message already holds 5 action rows.
+    pub fn add_action_row(mut self, row: ActionRow) -> Self {
+        let components = self.message.components.get_or_insert_with(Vec::new);
+        assert!(components.len() < 5, "a message holds at most 5 action rows");
+        components.push(Component::ActionRow(row));
+        self
+    }
+
+    /// Adds a component to the message, such as a [super::Container] built with [super::ContainerBuilder].
+    ///
+    /// This marks the message with [super::MessageFlags::IS_COMPONENTS_V2], so it can no longer have content or
+    /// embeds. Up to 40 components can be used, counting nested ones.
+    pub fn add_component(mut self, component: impl Into<Component>) -> Self {
+        self.message.flags |= MessageFlags::IS_COMPONENTS_V2;
+        self.message.components.get_or_insert_with(Vec::new).push(component.into());
+        self
+    }
+
+    /// Removes all components from the message, along with any added to this builder before.
+    ///
+    /// Without this or components being added, the edit leaves the components of the message as they are.
+    pub fn clear_components(mut self) -> Self {
+        self.message.components = Some(Vec::new());
+        self
+    }
+
     /// Decomposes the Edit builder into its base packet.
     /// 
     /// # Warning
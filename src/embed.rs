@@ -1,46 +1,52 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct EmbedFooter {
     pub text: Option<String>,
     pub icon_url: Option<String>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct EmbedMultimedia {
     pub url: Option<String>,
     pub height: Option<i32>,
     pub width: Option<i32>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct EmbedProvider {
     pub name: Option<String>,
     pub url: Option<String>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct EmbedAuthor {
     pub name: Option<String>,
     pub url: Option<String>,
     pub icon_url: Option<String>,
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EmbedField {
     pub name: String,
     pub value: String,
+    #[serde(default)]
     pub inline: bool,
 }
 
 /// The whole embed struct. Contains all the data you may require for an embed.
 /// 
 /// Use the provided [super::EmbedBuilder] to aid you in constructing the object, rather than manually building it.
-#[derive(Clone, Serialize)]
+///
+/// Embeds can also be deserialized, for example from a JSON config file or from a message returned by discord.
+/// Missing fields fall back to their defaults and unknown fields (such as `proxy_url`) are ignored.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Embed {
     pub title: Option<String>,
+    /// The embed type, always `rich` for webhook embeds.
     #[serde(rename = "type")]
-    embed_type: String,
+    pub embed_type: String,
     pub description: Option<String>,
     pub url: Option<String>,
     pub color: i32,
@@ -93,3 +99,84 @@ impl Embed {
         }
     }
 }
+
+impl Default for Embed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An embed as discord returns it on a sent message, with proxy urls and other fields this crate does not model.
+    const DISCORD_EMBED: &str = r#"{
+        "type": "rich",
+        "title": "Temperature",
+        "description": "Boiler is running hot",
+        "color": 15548997,
+        "timestamp": "2026-10-19T07:00:00.000000+00:00",
+        "footer": {
+            "text": "Sensor 1",
+            "icon_url": "https://example.com/icon.png",
+            "proxy_icon_url": "https://images-ext-1.discordapp.net/external/icon.png"
+        },
+        "image": {
+            "url": "https://example.com/chart.png",
+            "proxy_url": "https://images-ext-1.discordapp.net/external/chart.png",
+            "width": 480,
+            "height": 160,
+            "content_type": "image/png",
+            "placeholder": "abc",
+            "placeholder_version": 1,
+            "flags": 0
+        },
+        "fields": [
+            {"name": "Now", "value": "72.5 °C", "inline": true},
+            {"name": "Limit", "value": "70 °C"}
+        ],
+        "content_scan_version": 2
+    }"#;
+
+    #[test]
+    fn deserializes_discord_embed() {
+        let embed: Embed = serde_json::from_str(DISCORD_EMBED).unwrap();
+        assert_eq!(embed.embed_type, "rich");
+        assert_eq!(embed.title.as_deref(), Some("Temperature"));
+        assert_eq!(embed.color, 15548997);
+        assert_eq!(embed.footer.text.as_deref(), Some("Sensor 1"));
+        assert_eq!(embed.image.width, Some(480));
+        assert_eq!(embed.thumbnail, EmbedMultimedia::default());
+        assert_eq!(embed.author, EmbedAuthor::default());
+        assert_eq!(
+            embed.fields,
+            [
+                EmbedField { name: "Now".into(), value: "72.5 °C".into(), inline: true },
+                EmbedField { name: "Limit".into(), value: "70 °C".into(), inline: false },
+            ]
+        );
+    }
+
+    #[test]
+    fn reserializing_is_lossless() {
+        let embed: Embed = serde_json::from_str(DISCORD_EMBED).unwrap();
+        let json = serde_json::to_string(&embed).unwrap();
+        let reparsed: Embed = serde_json::from_str(&json).unwrap();
+        assert_eq!(embed, reparsed);
+        assert_eq!(serde_json::to_string(&reparsed).unwrap(), json);
+    }
+
+    #[test]
+    fn missing_fields_fall_back_to_defaults() {
+        let embed: Embed = serde_json::from_str("{}").unwrap();
+        assert_eq!(embed, Embed::default());
+        assert_eq!(embed.embed_type, "rich");
+    }
+
+    #[test]
+    fn default_matches_new() {
+        assert_eq!(Embed::default(), Embed::new());
+        assert_eq!(Embed::default(), serde_json::from_str(&serde_json::to_string(&Embed::default()).unwrap()).unwrap());
+    }
+}
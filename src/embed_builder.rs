@@ -42,7 +42,7 @@ impl EmbedBuilder {
     /// use diswh_esp::EmbedBuilder;
     /// 
     /// let embed = EmbedBuilder::new()
-    ///     .with_description("Hello, world!");
+    ///     .with_description("Hello, world!")
     ///     .build();
     /// ```
     pub fn with_description(mut self, description: impl Into<String>) -> Self {
@@ -0,0 +1,95 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::Snowflake;
+
+/// An emoji shown on a component, either a unicode emoji or a custom emoji of a server.
+///
+/// With [Display](fmt::Display) it is formatted as markup for message content, such as `<:fire:123>`.
+///
+/// # Example
+/// ```
+/// use diswh_esp::PartialEmoji;
+///
+/// assert_eq!(PartialEmoji::custom("fire", 123456789012345678u64).to_string(), "<:fire:123456789012345678>");
+/// assert_eq!(PartialEmoji::unicode("🔥").to_string(), "🔥");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PartialEmoji {
+    /// The id of a custom emoji, [None] for unicode emojis.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Snowflake>,
+    /// The name of a custom emoji, or the unicode emoji itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub animated: bool,
+}
+
+impl PartialEmoji {
+    /// A unicode emoji, such as `"🔥"`.
+    ///
+    /// # Panics
+    /// Will panic if the provided `emoji` can not be converted into a [String]
+    pub fn unicode(emoji: impl Into<String>) -> Self {
+        Self {
+            id: None,
+            name: Some(emoji.into()),
+            animated: false,
+        }
+    }
+
+    /// A custom emoji of a server.
+    ///
+    /// # Panics
+    /// Will panic if the provided `name` can not be converted into a [String]
+    pub fn custom(name: impl Into<String>, id: impl Into<Snowflake>) -> Self {
+        Self {
+            id: Some(id.into()),
+            name: Some(name.into()),
+            animated: false,
+        }
+    }
+
+    /// Marks a custom emoji as animated.
+    pub fn with_animated(mut self, animated: bool) -> Self {
+        self.animated = animated;
+        self
+    }
+}
+
+impl fmt::Display for PartialEmoji {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name.as_deref().unwrap_or("_");
+        match self.id {
+            Some(id) if self.animated => write!(f, "<a:{}:{}>", name, id),
+            Some(id) => write!(f, "<:{}:{}>", name, id),
+            None => f.write_str(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emoji_use_discord_markup() {
+        assert_eq!(PartialEmoji::unicode("🔥").to_string(), "🔥");
+        assert_eq!(PartialEmoji::custom("pump", 41771983429993937u64).to_string(), "<:pump:41771983429993937>");
+        assert_eq!(
+            PartialEmoji::custom("spinner", 41771983429993937u64)
+                .with_animated(true)
+                .to_string(),
+            "<a:spinner:41771983429993937>"
+        );
+        // Discord leaves out the name of deleted custom emoji.
+        let nameless = PartialEmoji {
+            name: None,
+            ..PartialEmoji::custom("gone", 1u64)
+        };
+        assert_eq!(nameless.to_string(), "<:_:1>");
+    }
+}
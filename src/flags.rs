@@ -0,0 +1,15 @@
+/// A struct containing the message flags a webhook may set.
+///
+/// Flags can be combined with `|`.
+pub struct MessageFlags;
+
+impl MessageFlags {
+    /// Do not include any embeds when serializing this message.
+    pub const SUPPRESS_EMBEDS: u64 = 1 << 2;
+    /// Do not trigger push and desktop notifications for this message.
+    pub const SUPPRESS_NOTIFICATIONS: u64 = 1 << 12;
+    /// Lay the message out with components, such as [super::Container], instead of content and embeds.
+    ///
+    /// Can not be removed again once set on a message.
+    pub const IS_COMPONENTS_V2: u64 = 1 << 15;
+}
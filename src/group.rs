@@ -0,0 +1,273 @@
+use std::{borrow::Cow, thread};
+
+use anyhow::anyhow;
+
+use super::{client::DEFAULT_STACK_SIZE, MessagePacket, Snowflake, WebhookBuilder, WebhookResponse};
+
+/// Changes applied to a message before it is sent to one target of a [WebhookGroup].
+#[derive(Clone, Debug, Default)]
+pub struct TargetOverrides {
+    pub username: Option<String>,
+    pub avatar_url: Option<String>,
+    pub thread_id: Option<Snowflake>,
+}
+
+impl TargetOverrides {
+    /// Constructs overrides that leave the message untouched.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends the message to this target with a different username.
+    ///
+    /// # Panics
+    /// Will panic if the provided `username` can not be converted into a [String]
+    pub fn with_username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    /// Sends the message to this target with a different avatar.
+    ///
+    /// # Panics
+    /// Will panic if the provided `avatar_url` can not be converted into a [String]
+    pub fn with_avatar_url(mut self, avatar_url: impl Into<String>) -> Self {
+        self.avatar_url = Some(avatar_url.into());
+        self
+    }
+
+    /// Posts the message into a thread of the target channel.
+    pub fn with_thread_id(mut self, thread_id: impl Into<Snowflake>) -> Self {
+        self.thread_id = Some(thread_id.into());
+        self
+    }
+}
+
+/// One webhook of a [WebhookGroup].
+#[derive(Clone)]
+pub struct WebhookTarget {
+    pub name: String,
+    pub webhook: WebhookBuilder,
+    pub overrides: TargetOverrides,
+}
+
+impl WebhookTarget {
+    fn send(&self, packet: &MessagePacket) -> anyhow::Result<WebhookResponse> {
+        let (webhook, packet) = self.prepare(packet);
+        webhook.send_message_with_response(&packet)
+    }
+
+    /// Returns the webhook and the message as this target gets them, with its overrides applied.
+    fn prepare(&self, packet: &MessagePacket) -> (Cow<'_, WebhookBuilder>, MessagePacket) {
+        let mut packet = packet.clone();
+        if let Some(username) = &self.overrides.username {
+            packet.username = username.clone();
+        }
+        if let Some(avatar_url) = &self.overrides.avatar_url {
+            packet.avatar_url = avatar_url.clone();
+        }
+        let webhook = match self.overrides.thread_id {
+            Some(thread_id) => Cow::Owned(self.webhook.clone().with_thread_id(thread_id)),
+            None => Cow::Borrowed(&self.webhook),
+        };
+        (webhook, packet)
+    }
+}
+
+/// The outcome of sending a message to one target of a [WebhookGroup].
+#[derive(Debug)]
+pub struct TargetResult {
+    /// The name the target was added with.
+    pub name: String,
+    pub result: anyhow::Result<WebhookResponse>,
+}
+
+impl TargetResult {
+    /// Returns true when the request went through and discord responded with a 2xx status.
+    pub fn is_success(&self) -> bool {
+        matches!(&self.result, Ok(response) if response.is_success())
+    }
+}
+
+/// Sends the same message to several webhooks, such as an ops channel and a per site channel.
+///
+/// Every target gets its own [TargetResult], so one channel failing does not hide whether the others got the message.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{MessageBuilder, TargetOverrides, WebhookBuilder, WebhookGroup};
+///
+/// let group = WebhookGroup::new()
+///     .add_target("ops", WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"))
+///     .add_target_with(
+///         "site",
+///         WebhookBuilder::new("https://discord.com/api/webhooks/5678/token"),
+///         TargetOverrides::new().with_username("Site 12"),
+///     );
+///
+/// for target in group.send_message(&MessageBuilder::new("Pump 3 stopped", false).build()) {
+///     if !target.is_success() {
+///         println!("{} did not get the alert", target.name);
+///     }
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct WebhookGroup {
+    targets: Vec<WebhookTarget>,
+    concurrent: bool,
+}
+
+impl WebhookGroup {
+    /// Constructs an empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a webhook that gets the message as is.
+    ///
+    /// # Panics
+    /// Will panic if the provided `name` can not be converted into a [String]
+    pub fn add_target(self, name: impl Into<String>, webhook: WebhookBuilder) -> Self {
+        self.add_target_with(name, webhook, TargetOverrides::new())
+    }
+
+    /// Adds a webhook that gets the message with `overrides` applied.
+    ///
+    /// # Panics
+    /// Will panic if the provided `name` can not be converted into a [String]
+    pub fn add_target_with(mut self, name: impl Into<String>, webhook: WebhookBuilder, overrides: TargetOverrides) -> Self {
+        self.targets.push(WebhookTarget {
+            name: name.into(),
+            webhook,
+            overrides,
+        });
+        self
+    }
+
+    /// Sends to all targets at once, each on its own thread, instead of one after the other.
+    ///
+    /// Faster, at the cost of a TLS session and a 16 KiB stack per target at the same time.
+    pub fn with_concurrency(mut self, concurrent: bool) -> Self {
+        self.concurrent = concurrent;
+        self
+    }
+
+    /// Returns the targets of the group.
+    pub fn targets(&self) -> &[WebhookTarget] {
+        &self.targets
+    }
+
+    /// Sends `packet` to every target, returning the results in the order the targets were added.
+    pub fn send_message(&self, packet: &MessagePacket) -> Vec<TargetResult> {
+        if !self.concurrent {
+            return self
+                .targets
+                .iter()
+                .map(|target| TargetResult {
+                    name: target.name.clone(),
+                    result: target.send(packet),
+                })
+                .collect();
+        }
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .targets
+                .iter()
+                .map(|target| {
+                    thread::Builder::new()
+                        .name("diswh-group".into())
+                        .stack_size(DEFAULT_STACK_SIZE)
+                        .spawn_scoped(scope, move || target.send(packet))
+                })
+                .collect();
+            self.targets
+                .iter()
+                .zip(handles)
+                .map(|(target, handle)| TargetResult {
+                    name: target.name.clone(),
+                    result: match handle {
+                        Ok(handle) => handle
+                            .join()
+                            .unwrap_or_else(|_| Err(anyhow!("webhook request thread panicked"))),
+                        Err(e) => Err(e.into()),
+                    },
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBuilder;
+
+    fn webhook(id: u64) -> WebhookBuilder {
+        WebhookBuilder::new(format!("https://discord.com/api/webhooks/{}/token", id))
+    }
+
+    fn group() -> WebhookGroup {
+        WebhookGroup::new()
+            .add_target("ops", webhook(1))
+            .add_target_with(
+                "site",
+                webhook(2),
+                TargetOverrides::new()
+                    .with_username("Site 12")
+                    .with_avatar_url("https://example.com/site.png")
+                    .with_thread_id(77u64),
+            )
+            .add_target_with("named", webhook(3), TargetOverrides::new().with_username("Named"))
+    }
+
+    #[test]
+    fn overrides_apply_only_to_their_target() {
+        let group = group();
+        let packet = MessageBuilder::new("Pump 3 stopped", false)
+            .with_username("Pump")
+            .build();
+        let prepared: Vec<_> = group.targets().iter().map(|target| target.prepare(&packet)).collect();
+
+        let (ops_webhook, ops_packet) = &prepared[0];
+        assert_eq!(ops_packet.username, "Pump");
+        assert_eq!(ops_packet.avatar_url, packet.avatar_url);
+        assert_eq!(ops_webhook.url().unwrap().query("thread_id"), None);
+        assert!(matches!(ops_webhook, Cow::Borrowed(_)));
+
+        let (site_webhook, site_packet) = &prepared[1];
+        assert_eq!(site_packet.username, "Site 12");
+        assert_eq!(site_packet.avatar_url, "https://example.com/site.png");
+        assert_eq!(site_packet.content, "Pump 3 stopped");
+        assert_eq!(site_webhook.url().unwrap().query("thread_id"), Some("77"));
+
+        let (named_webhook, named_packet) = &prepared[2];
+        assert_eq!(named_packet.username, "Named");
+        assert_eq!(named_packet.avatar_url, packet.avatar_url);
+        assert_eq!(named_webhook.url().unwrap().query("thread_id"), None);
+
+        // The shared packet and the targets' own webhooks are left as they were.
+        assert_eq!(packet.username, "Pump");
+        assert_eq!(group.targets()[1].webhook.url().unwrap().query("thread_id"), None);
+    }
+
+    #[test]
+    fn results_are_named_after_their_target() {
+        // Requests to hosts other than discord fail before touching the network, naming the host in the error.
+        let group = WebhookGroup::new()
+            .add_target("first", WebhookBuilder::new("https://first.example/api/webhooks/1/t"))
+            .add_target("second", WebhookBuilder::new("https://second.example/api/webhooks/2/t"))
+            .add_target("third", WebhookBuilder::new("https://third.example/api/webhooks/3/t"));
+        let packet = MessageBuilder::new("Pump 3 stopped", false).build();
+        for concurrent in [false, true] {
+            let results = group.clone().with_concurrency(concurrent).send_message(&packet);
+            let names: Vec<_> = results.iter().map(|result| result.name.as_str()).collect();
+            assert_eq!(names, ["first", "second", "third"]);
+            for result in &results {
+                assert!(!result.is_success());
+                let error = result.result.as_ref().unwrap_err().to_string();
+                assert!(error.contains(&format!("{}.example", result.name)), "{}", error);
+            }
+        }
+    }
+}
@@ -0,0 +1,463 @@
+use std::{
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use log::error;
+
+use super::{
+    device, util::format_duration, Color, EditMessageBuilder, Embed, EmbedBuilder, LiveMessage, MessageBuilder,
+    MessagePacket, Storage, WebhookBuilder, WebhookResponse,
+};
+
+type Sender<'a> = dyn FnMut(&MessagePacket) -> anyhow::Result<WebhookResponse> + 'a;
+
+/// Stored beats from before this time, 2020-09-13, were taken before the clock was set and are ignored.
+const EARLIEST_VALID_BEAT: u64 = 1_600_000_000;
+
+/// Provides the values shown in a heartbeat.
+///
+/// [DeviceMetrics] reads them from the chip, other implementations can return fixed values when running on a host.
+pub trait Metrics {
+    /// Time since the device booted.
+    fn uptime(&self) -> Duration;
+
+    /// Bytes of heap currently free, if known.
+    fn free_heap(&self) -> Option<u32> {
+        None
+    }
+
+    /// Signal strength of the network connection in dBm, if known.
+    fn rssi(&self) -> Option<i8> {
+        None
+    }
+}
+
+/// Reads heartbeat metrics from ESP-IDF.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DeviceMetrics;
+
+impl Metrics for DeviceMetrics {
+    fn uptime(&self) -> Duration {
+        device::uptime()
+    }
+
+    fn free_heap(&self) -> Option<u32> {
+        Some(device::free_heap())
+    }
+
+    fn rssi(&self) -> Option<i8> {
+        device::rssi()
+    }
+}
+
+/// Posts an "alive" embed at a fixed interval, working as a dead man's switch for a device.
+///
+/// Every beat shows the uptime, free heap, signal strength and a sequence number, gaps in the sequence show beats that
+/// never arrived. When a beat goes through after at least one interval was missed, such as after the network came
+/// back, a separate "offline for 12 minutes" message is sent first.
+///
+/// By default every beat is a new message, with [Heartbeat::with_live_message] a single message is edited instead.
+/// With [Heartbeat::with_storage] the last beat survives reboots, so the time the device was off is reported too.
+/// Offline time is measured with the wall clock, which has to be set, such as through SNTP, before the first beat.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use diswh_esp::{Heartbeat, LiveMessage, WebhookBuilder};
+///
+/// let webhook = WebhookBuilder::new("https://discord.com/api/webhooks/1234/token");
+/// Heartbeat::new(webhook.clone(), "pump-controller-3")
+///     .with_interval(Duration::from_secs(300))
+///     .with_live_message(LiveMessage::new(webhook))
+///     .spawn()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct Heartbeat {
+    webhook: WebhookBuilder,
+    device: String,
+    interval: Duration,
+    metrics: Box<dyn Metrics + Send>,
+    live: Option<LiveMessage>,
+    report_missed: bool,
+    sequence: u64,
+    storage: Option<(Box<dyn Storage + Send>, String)>,
+    loaded: bool,
+    last_attempt: Option<Instant>,
+    last_success: Option<SystemTime>,
+}
+
+impl Heartbeat {
+    /// Constructs a heartbeat posting every minute, `device` is shown in every beat to tell devices apart.
+    ///
+    /// # Panics
+    /// Will panic if the provided `device` can not be converted into a [String]
+    pub fn new(webhook: WebhookBuilder, device: impl Into<String>) -> Self {
+        Self {
+            webhook,
+            device: device.into(),
+            interval: Duration::from_secs(60),
+            metrics: Box::new(DeviceMetrics),
+            live: None,
+            report_missed: true,
+            sequence: 0,
+            storage: None,
+            loaded: false,
+            last_attempt: None,
+            last_success: None,
+        }
+    }
+
+    /// Sets the time between two beats.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets where the uptime, free heap and signal strength come from, defaults to [DeviceMetrics].
+    pub fn with_metrics(mut self, metrics: impl Metrics + Send + 'static) -> Self {
+        self.metrics = Box::new(metrics);
+        self
+    }
+
+    /// Edits `message` on every beat instead of posting a new message.
+    ///
+    /// The minimum interval of `message` does not apply, beats are already spaced by the heartbeat's interval.
+    pub fn with_live_message(mut self, message: LiveMessage) -> Self {
+        self.live = Some(message);
+        self
+    }
+
+    /// Sets whether a message is sent when beats were missed, defaults to true.
+    pub fn with_missed_reports(mut self, report_missed: bool) -> Self {
+        self.report_missed = report_missed;
+        self
+    }
+
+    /// Keeps the time and sequence number of the last beat in `storage` under `key`, so missed beats are still
+    /// reported after a reboot and the sequence carries on where it left off.
+    ///
+    /// # Panics
+    /// Will panic if the provided `key` can not be converted into a [String]
+    pub fn with_storage(mut self, storage: impl Storage + Send + 'static, key: impl Into<String>) -> Self {
+        self.storage = Some((Box::new(storage), key.into()));
+        self
+    }
+
+    /// The sequence number of the last beat.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Beats if the interval passed since the last attempt.
+    ///
+    /// Returns whether a beat was attempted.
+    pub fn tick(&mut self) -> anyhow::Result<bool> {
+        if self
+            .last_attempt
+            .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return Ok(false);
+        }
+        self.beat()?;
+        Ok(true)
+    }
+
+    /// Beats right away, reporting missed beats first.
+    pub fn beat(&mut self) -> anyhow::Result<()> {
+        self.last_attempt = Some(Instant::now());
+        let webhook = self.webhook.clone();
+        self.beat_at(SystemTime::now(), &mut |packet| webhook.send_message_with_response(packet))
+    }
+
+    /// Moves the heartbeat to its own thread, beating until the program exits.
+    ///
+    /// Failed beats are logged and retried at the next interval.
+    pub fn spawn(mut self) -> anyhow::Result<JoinHandle<()>> {
+        let handle = thread::Builder::new()
+            .name("diswh-heartbeat".into())
+            .stack_size(super::client::DEFAULT_STACK_SIZE)
+            .spawn(move || loop {
+                if let Err(e) = self.tick() {
+                    error!("Failed to send heartbeat: {}", e);
+                }
+                let next = self
+                    .last_attempt
+                    .map(|last| self.interval.saturating_sub(last.elapsed()))
+                    .unwrap_or_default();
+                thread::sleep(next);
+            })?;
+        Ok(handle)
+    }
+
+    /// Beats at `now`, sending new messages through `send`.
+    fn beat_at(&mut self, now: SystemTime, send: &mut Sender) -> anyhow::Result<()> {
+        self.load_last_beat()?;
+        self.sequence += 1;
+
+        if let Some(offline) = self.last_success.and_then(|last| now.duration_since(last).ok()) {
+            if self.report_missed && offline >= self.interval * 2 {
+                let response = send(&MessageBuilder::new("", false).add_embed(self.offline_embed(offline)).build())?;
+                if !response.is_success() {
+                    bail!("sending missed heartbeat report failed with status {}", response.status);
+                }
+            }
+        }
+
+        let embed = self.embed();
+        match &mut self.live {
+            Some(live) => {
+                if !live.force_update(EditMessageBuilder::new("").add_embed(embed).build())? {
+                    bail!("heartbeat skipped while rate limited");
+                }
+            }
+            None => {
+                let response = send(&MessageBuilder::new("", false).add_embed(embed).build())?;
+                if !response.is_success() {
+                    bail!("sending heartbeat failed with status {}", response.status);
+                }
+            }
+        }
+        self.last_success = Some(now);
+        self.store_last_beat(now)
+    }
+
+    fn load_last_beat(&mut self) -> anyhow::Result<()> {
+        if self.loaded {
+            return Ok(());
+        }
+        if let Some((storage, key)) = &mut self.storage {
+            if let Some(bytes) = storage.load(key)? {
+                if let Ok(bytes) = <[u8; 16]>::try_from(bytes.as_slice()) {
+                    let (time, sequence) = bytes.split_at(8);
+                    let time = u64::from_le_bytes(time.try_into()?);
+                    if time >= EARLIEST_VALID_BEAT {
+                        self.last_success = Some(UNIX_EPOCH + Duration::from_secs(time));
+                    }
+                    self.sequence = u64::from_le_bytes(sequence.try_into()?);
+                }
+            }
+        }
+        self.loaded = true;
+        Ok(())
+    }
+
+    fn store_last_beat(&mut self, now: SystemTime) -> anyhow::Result<()> {
+        let Some((storage, key)) = &mut self.storage else {
+            return Ok(());
+        };
+        let time = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&time.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.sequence.to_le_bytes());
+        storage.store(key, &bytes)
+    }
+
+    fn offline_embed(&self, offline: Duration) -> Embed {
+        let missed = offline.as_secs_f64() / self.interval.as_secs_f64();
+        EmbedBuilder::new()
+            .with_title(format!("{} is back", self.device))
+            .with_description(format!(
+                "Offline for {}, {} heartbeats were missed.",
+                format_duration(offline),
+                missed as u64 - 1
+            ))
+            .with_color(Color::ORANGE)
+            .build()
+    }
+
+    fn embed(&self) -> Embed {
+        let mut embed = EmbedBuilder::new()
+            .with_title(format!("{} is alive", self.device))
+            .with_color(Color::GREEN)
+            .add_field("Uptime", format_duration(self.metrics.uptime()), true);
+        if let Some(free_heap) = self.metrics.free_heap() {
+            embed = embed.add_field("Free heap", format!("{} KiB", free_heap / 1024), true);
+        }
+        if let Some(rssi) = self.metrics.rssi() {
+            embed = embed.add_field("RSSI", format!("{} dBm", rssi), true);
+        }
+        embed
+            .add_field("Sequence", format!("#{}", self.sequence), true)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStorage;
+
+    struct FakeMetrics;
+
+    impl Metrics for FakeMetrics {
+        fn uptime(&self) -> Duration {
+            Duration::from_secs(7200)
+        }
+
+        fn free_heap(&self) -> Option<u32> {
+            Some(150 * 1024)
+        }
+
+        fn rssi(&self) -> Option<i8> {
+            Some(-61)
+        }
+    }
+
+    struct NoMetrics;
+
+    impl Metrics for NoMetrics {
+        fn uptime(&self) -> Duration {
+            Duration::from_secs(30)
+        }
+    }
+
+    fn heartbeat() -> Heartbeat {
+        Heartbeat::new(WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"), "pump-3")
+            .with_interval(Duration::from_secs(60))
+            .with_metrics(FakeMetrics)
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_700_000_000 + seconds)
+    }
+
+    /// Beats at `now`, returning the embeds of every message sent.
+    fn beat(heartbeat: &mut Heartbeat, now: SystemTime, status: u16) -> (anyhow::Result<()>, Vec<Embed>) {
+        let mut sent = Vec::new();
+        let result = heartbeat.beat_at(now, &mut |packet: &MessagePacket| {
+            sent.extend(packet.embeds.iter().cloned());
+            Ok(WebhookResponse {
+                status,
+                body: Vec::new(),
+                truncated: false,
+            })
+        });
+        (result, sent)
+    }
+
+    fn field<'a>(embed: &'a Embed, name: &str) -> Option<&'a str> {
+        embed
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| field.value.as_str())
+    }
+
+    #[test]
+    fn beat_shows_metrics_and_sequence() {
+        let mut heartbeat = heartbeat();
+        let (result, sent) = beat(&mut heartbeat, at(0), 204);
+        result.unwrap();
+        assert_eq!(sent.len(), 1);
+        let embed = &sent[0];
+        assert_eq!(embed.title.as_deref(), Some("pump-3 is alive"));
+        assert_eq!(embed.color, Color::GREEN);
+        assert_eq!(field(embed, "Uptime"), Some("2 hours"));
+        assert_eq!(field(embed, "Free heap"), Some("150 KiB"));
+        assert_eq!(field(embed, "RSSI"), Some("-61 dBm"));
+        assert_eq!(field(embed, "Sequence"), Some("#1"));
+        assert!(embed.fields.iter().all(|field| field.inline));
+    }
+
+    #[test]
+    fn unknown_metrics_are_left_out() {
+        let mut heartbeat = heartbeat().with_metrics(NoMetrics);
+        let (result, sent) = beat(&mut heartbeat, at(0), 204);
+        result.unwrap();
+        let names: Vec<_> = sent[0].fields.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(names, ["Uptime", "Sequence"]);
+        assert_eq!(field(&sent[0], "Uptime"), Some("30 seconds"));
+    }
+
+    #[test]
+    fn sequence_counts_failed_beats() {
+        let mut heartbeat = heartbeat();
+        beat(&mut heartbeat, at(0), 204).0.unwrap();
+        assert!(beat(&mut heartbeat, at(60), 500).0.is_err());
+        let (result, sent) = beat(&mut heartbeat, at(110), 204);
+        result.unwrap();
+        assert_eq!(heartbeat.sequence(), 3);
+        // The failed beat left a gap, but less than two intervals since the last beat is not reported as missed.
+        assert_eq!(sent.len(), 1);
+        assert_eq!(field(&sent[0], "Sequence"), Some("#3"));
+    }
+
+    #[test]
+    fn missed_beats_are_reported_first() {
+        let mut heartbeat = heartbeat();
+        beat(&mut heartbeat, at(0), 204).0.unwrap();
+        let (result, sent) = beat(&mut heartbeat, at(720), 204);
+        result.unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0].title.as_deref(), Some("pump-3 is back"));
+        assert_eq!(sent[0].color, Color::ORANGE);
+        assert_eq!(
+            sent[0].description.as_deref(),
+            Some("Offline for 12 minutes, 11 heartbeats were missed.")
+        );
+        assert_eq!(sent[1].title.as_deref(), Some("pump-3 is alive"));
+
+        let mut quiet = heartbeat.with_missed_reports(false);
+        let (result, sent) = beat(&mut quiet, at(7200), 204);
+        result.unwrap();
+        assert_eq!(sent.len(), 1);
+    }
+
+    #[test]
+    fn failed_report_skips_the_beat() {
+        let mut heartbeat = heartbeat();
+        beat(&mut heartbeat, at(0), 204).0.unwrap();
+        let (result, sent) = beat(&mut heartbeat, at(720), 500);
+        assert!(result.unwrap_err().to_string().contains("missed heartbeat report"));
+        assert_eq!(sent.len(), 1);
+        // The report is retried with the next beat, still measured from the last beat that went through.
+        let (result, sent) = beat(&mut heartbeat, at(780), 204);
+        result.unwrap();
+        assert_eq!(sent[0].description.as_deref(), Some("Offline for 13 minutes, 12 heartbeats were missed."));
+    }
+
+    #[test]
+    fn last_beat_survives_a_reboot() {
+        let mut heartbeat = heartbeat().with_storage(MemoryStorage::new(), "heartbeat");
+        beat(&mut heartbeat, at(0), 204).0.unwrap();
+        beat(&mut heartbeat, at(60), 204).0.unwrap();
+        let (storage, key) = heartbeat.storage.as_mut().unwrap();
+        let bytes = storage.load(key).unwrap().unwrap();
+        let mut storage = MemoryStorage::new();
+        storage.store("heartbeat", &bytes).unwrap();
+
+        let mut rebooted = self::heartbeat().with_storage(storage, "heartbeat");
+        let (result, sent) = beat(&mut rebooted, at(780), 204);
+        result.unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(
+            sent[0].description.as_deref(),
+            Some("Offline for 12 minutes, 11 heartbeats were missed.")
+        );
+        assert_eq!(field(&sent[1], "Sequence"), Some("#3"));
+    }
+
+    #[test]
+    fn beats_from_before_the_clock_was_set_are_ignored() {
+        let mut storage = MemoryStorage::new();
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&10u64.to_le_bytes());
+        bytes[8..].copy_from_slice(&4u64.to_le_bytes());
+        storage.store("heartbeat", &bytes).unwrap();
+        storage.store("broken", &[1, 2, 3]).unwrap();
+
+        let mut heartbeat = heartbeat().with_storage(storage.clone(), "heartbeat");
+        let (result, sent) = beat(&mut heartbeat, at(0), 204);
+        result.unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(field(&sent[0], "Sequence"), Some("#5"));
+
+        let mut heartbeat = self::heartbeat().with_storage(storage, "broken");
+        let (result, sent) = beat(&mut heartbeat, at(0), 204);
+        result.unwrap();
+        assert_eq!(field(&sent[0], "Sequence"), Some("#1"));
+    }
+}
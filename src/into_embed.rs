@@ -0,0 +1,38 @@
+use super::Embed;
+
+/// Turns a value, such as a telemetry struct, into an embed.
+///
+/// With the `derive` feature this can be derived with `#[derive(IntoEmbed)]`, making every field an embed field.
+/// Attributes rename fields, add units and formatting, skip fields, or use fields as the title, description, footer
+/// or color. See the `diswh-esp-derive` crate for the full list.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{Embed, EmbedBuilder, IntoEmbed, MessageBuilder};
+///
+/// struct Reading {
+///     sensor: String,
+///     temperature: f32,
+/// }
+///
+/// // What `#[derive(IntoEmbed)]` with `#[embed(title)]` on `sensor` and `#[embed(unit = "°C")]` on `temperature`
+/// // generates.
+/// impl IntoEmbed for Reading {
+///     fn to_embed(&self) -> Embed {
+///         EmbedBuilder::new()
+///             .with_title(self.sensor.to_string())
+///             .add_field("Temperature", format!("{} °C", self.temperature), true)
+///             .build()
+///     }
+/// }
+///
+/// let reading = Reading { sensor: "Greenhouse".into(), temperature: 21.4 };
+/// let message = MessageBuilder::new("", false).add_embed(reading.to_embed()).build();
+/// ```
+pub trait IntoEmbed {
+    /// Builds an embed showing this value.
+    fn to_embed(&self) -> Embed;
+}
+
+#[cfg(feature = "derive")]
+pub use diswh_esp_derive::IntoEmbed;
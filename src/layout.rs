@@ -0,0 +1,435 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::Component;
+
+/// A piece of media shown by a component, either a url or an attachment of the message as `attachment://name.png`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaItem {
+    pub url: String,
+}
+
+impl MediaItem {
+    /// # Panics
+    /// Will panic if the provided `url` can not be converted into a [String]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+/// Markdown text, the layout counterpart of a message's content.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextDisplay {
+    pub content: String,
+}
+
+impl TextDisplay {
+    /// # Panics
+    /// Will panic if the provided `content` can not be converted into a [String]
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+        }
+    }
+}
+
+/// A small image, used as the accessory of a [Section].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thumbnail {
+    pub media: MediaItem,
+    /// Alt text of the image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub spoiler: bool,
+}
+
+impl Thumbnail {
+    /// # Panics
+    /// Will panic if the provided `url` can not be converted into a [String]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            media: MediaItem::new(url),
+            description: None,
+            spoiler: false,
+        }
+    }
+
+    /// Sets the alt text of the image.
+    ///
+    /// # Panics
+    /// Will panic if the provided `description` can not be converted into a [String]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Hides the image until it is clicked.
+    pub fn with_spoiler(mut self, spoiler: bool) -> Self {
+        self.spoiler = spoiler;
+        self
+    }
+}
+
+/// Up to 3 text displays with a thumbnail or button next to them.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{Section, Thumbnail};
+///
+/// let section = Section::new(Thumbnail::new("https://example.com/boiler.png"))
+///     .add_text("**Boiler**")
+///     .add_text("72 °C");
+///
+/// assert_eq!(
+///     serde_json::to_value(&section).unwrap(),
+///     serde_json::json!({
+///         "components": [
+///             { "type": 10, "content": "**Boiler**" },
+///             { "type": 10, "content": "72 °C" }
+///         ],
+///         "accessory": { "type": 11, "media": { "url": "https://example.com/boiler.png" } }
+///     })
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Section {
+    pub components: Vec<Component>,
+    /// A [Thumbnail] or [super::Button].
+    pub accessory: Box<Component>,
+}
+
+impl Section {
+    /// Constructs a section without text, `accessory` must be a [Thumbnail] or [super::Button].
+    pub fn new(accessory: impl Into<Component>) -> Self {
+        Self {
+            components: Vec::new(),
+            accessory: Box::new(accessory.into()),
+        }
+    }
+
+    /// Adds a line of markdown text to the section.
+    ///
+    /// # Panics
+    /// Will panic if the section already holds 3 text displays.
+    pub fn add_text(mut self, content: impl Into<String>) -> Self {
+        assert!(self.components.len() < 3, "a section holds at most 3 text displays");
+        self.components.push(Component::TextDisplay(TextDisplay::new(content)));
+        self
+    }
+}
+
+/// One image or video of a [MediaGallery].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaGalleryItem {
+    pub media: MediaItem,
+    /// Alt text of the media.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub spoiler: bool,
+}
+
+impl MediaGalleryItem {
+    /// # Panics
+    /// Will panic if the provided `url` can not be converted into a [String]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            media: MediaItem::new(url),
+            description: None,
+            spoiler: false,
+        }
+    }
+
+    /// Sets the alt text of the media.
+    ///
+    /// # Panics
+    /// Will panic if the provided `description` can not be converted into a [String]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Hides the media until it is clicked.
+    pub fn with_spoiler(mut self, spoiler: bool) -> Self {
+        self.spoiler = spoiler;
+        self
+    }
+}
+
+/// A grid of up to 10 images or videos.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaGallery {
+    pub items: Vec<MediaGalleryItem>,
+}
+
+impl MediaGallery {
+    /// Constructs an empty gallery.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an image or video by its url.
+    ///
+    /// # Panics
+    /// Will panic if the gallery already holds 10 items.
+    pub fn add_url(self, url: impl Into<String>) -> Self {
+        self.add_item(MediaGalleryItem::new(url))
+    }
+
+    /// Adds an image or video.
+    ///
+    /// # Panics
+    /// Will panic if the gallery already holds 10 items.
+    pub fn add_item(mut self, item: MediaGalleryItem) -> Self {
+        assert!(self.items.len() < 10, "a media gallery holds at most 10 items");
+        self.items.push(item);
+        self
+    }
+}
+
+/// An attached file, shown as a download. The url must point at an attachment, as `attachment://log.txt`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileComponent {
+    pub file: MediaItem,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub spoiler: bool,
+}
+
+impl FileComponent {
+    /// # Panics
+    /// Will panic if the provided `url` can not be converted into a [String]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            file: MediaItem::new(url),
+            spoiler: false,
+        }
+    }
+}
+
+/// How much room a [Separator] takes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum SeparatorSpacing {
+    #[default]
+    Small = 1,
+    Large = 2,
+}
+
+impl Serialize for SeparatorSpacing {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for SeparatorSpacing {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match u8::deserialize(deserializer)? {
+            1 => Self::Small,
+            2 => Self::Large,
+            spacing => return Err(de::Error::custom(format!("unknown separator spacing {}", spacing))),
+        })
+    }
+}
+
+/// Vertical space between components, with or without a line.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Separator {
+    #[serde(default = "Separator::default_divider")]
+    pub divider: bool,
+    #[serde(default)]
+    pub spacing: SeparatorSpacing,
+}
+
+impl Separator {
+    /// Constructs a small separator, drawing a line when `divider` is set.
+    pub fn new(divider: bool) -> Self {
+        Self {
+            divider,
+            spacing: SeparatorSpacing::Small,
+        }
+    }
+
+    /// Sets how much room the separator takes.
+    pub fn with_spacing(mut self, spacing: SeparatorSpacing) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    fn default_divider() -> bool {
+        true
+    }
+}
+
+/// A box around components with an optional accent color, the layout counterpart of an embed.
+///
+/// Use the provided [super::ContainerBuilder] to aid you in constructing the object.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Container {
+    pub components: Vec<Component>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accent_color: Option<i32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub spoiler: bool,
+}
+
+impl From<TextDisplay> for Component {
+    fn from(text: TextDisplay) -> Self {
+        Self::TextDisplay(text)
+    }
+}
+
+impl From<Thumbnail> for Component {
+    fn from(thumbnail: Thumbnail) -> Self {
+        Self::Thumbnail(thumbnail)
+    }
+}
+
+impl From<Section> for Component {
+    fn from(section: Section) -> Self {
+        Self::Section(section)
+    }
+}
+
+impl From<MediaGallery> for Component {
+    fn from(gallery: MediaGallery) -> Self {
+        Self::MediaGallery(gallery)
+    }
+}
+
+impl From<FileComponent> for Component {
+    fn from(file: FileComponent) -> Self {
+        Self::File(file)
+    }
+}
+
+impl From<Separator> for Component {
+    fn from(separator: Separator) -> Self {
+        Self::Separator(separator)
+    }
+}
+
+impl From<Container> for Component {
+    fn from(container: Container) -> Self {
+        Self::Container(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn text_display_serializes_as_type_10() {
+        assert_eq!(
+            serde_json::to_value(Component::from(TextDisplay::new("**Boiler** 72 °C"))).unwrap(),
+            json!({ "type": 10, "content": "**Boiler** 72 °C" })
+        );
+    }
+
+    #[test]
+    fn thumbnail_serializes_as_type_11() {
+        assert_eq!(
+            serde_json::to_value(Component::from(Thumbnail::new("attachment://plant.png"))).unwrap(),
+            json!({ "type": 11, "media": { "url": "attachment://plant.png" } })
+        );
+        let thumbnail = Thumbnail::new("https://example.com/plant.png")
+            .with_description("A tomato plant")
+            .with_spoiler(true);
+        assert_eq!(
+            serde_json::to_value(Component::from(thumbnail)).unwrap(),
+            json!({
+                "type": 11,
+                "media": { "url": "https://example.com/plant.png" },
+                "description": "A tomato plant",
+                "spoiler": true
+            })
+        );
+    }
+
+    #[test]
+    fn section_serializes_as_type_9() {
+        let section = Section::new(crate::Button::link("Open", "https://example.com"))
+            .add_text("**Pump 3**")
+            .add_text("Running");
+        assert_eq!(
+            serde_json::to_value(Component::from(section)).unwrap(),
+            json!({
+                "type": 9,
+                "components": [
+                    { "type": 10, "content": "**Pump 3**" },
+                    { "type": 10, "content": "Running" }
+                ],
+                "accessory": { "type": 2, "style": 5, "label": "Open", "url": "https://example.com" }
+            })
+        );
+    }
+
+    #[test]
+    fn media_gallery_serializes_as_type_12() {
+        let gallery = MediaGallery::new()
+            .add_url("https://example.com/1.png")
+            .add_item(
+                MediaGalleryItem::new("attachment://2.png")
+                    .with_description("Second camera")
+                    .with_spoiler(true),
+            );
+        assert_eq!(
+            serde_json::to_value(Component::from(gallery)).unwrap(),
+            json!({
+                "type": 12,
+                "items": [
+                    { "media": { "url": "https://example.com/1.png" } },
+                    { "media": { "url": "attachment://2.png" }, "description": "Second camera", "spoiler": true }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn file_serializes_as_type_13() {
+        assert_eq!(
+            serde_json::to_value(Component::from(FileComponent::new("attachment://log.txt"))).unwrap(),
+            json!({ "type": 13, "file": { "url": "attachment://log.txt" } })
+        );
+    }
+
+    #[test]
+    fn separator_serializes_as_type_14() {
+        assert_eq!(
+            serde_json::to_value(Component::from(Separator::new(false))).unwrap(),
+            json!({ "type": 14, "divider": false, "spacing": 1 })
+        );
+        assert_eq!(
+            serde_json::to_value(Component::from(Separator::new(true).with_spacing(SeparatorSpacing::Large))).unwrap(),
+            json!({ "type": 14, "divider": true, "spacing": 2 })
+        );
+        // Discord leaves out the defaults, a divider with small spacing.
+        assert_eq!(
+            serde_json::from_value::<Component>(json!({ "type": 14 })).unwrap(),
+            Component::Separator(Separator::new(true))
+        );
+        assert!(serde_json::from_value::<Component>(json!({ "type": 14, "spacing": 3 })).is_err());
+    }
+
+    #[test]
+    fn layout_components_round_trip() {
+        let components: Vec<Component> = vec![
+            TextDisplay::new("text").into(),
+            Section::new(Thumbnail::new("https://example.com/a.png")).add_text("a").into(),
+            MediaGallery::new().add_url("https://example.com/b.png").into(),
+            FileComponent::new("attachment://c.txt").into(),
+            Separator::new(false).with_spacing(SeparatorSpacing::Large).into(),
+        ];
+        let value = serde_json::to_value(&components).unwrap();
+        assert_eq!(serde_json::from_value::<Vec<Component>>(value).unwrap(), components);
+    }
+}
@@ -1,88 +1,424 @@
+pub use alert::*;
+pub use attachment::*;
+#[cfg(feature = "chart")]
+pub use chart::*;
+pub use client::*;
 pub use color::*;
+pub use component::*;
+pub use container_builder::*;
+pub use config::*;
+pub use crash::*;
+pub use device::*;
 pub use edit::*;
 pub use edit_builder::*;
 pub use embed::*;
 pub use embed_builder::*;
+pub use emoji::*;
+pub use flags::*;
+pub use group::*;
+pub use heartbeat::*;
+pub use into_embed::*;
+pub use layout::*;
+pub use live_message::*;
+pub use logger::*;
 pub use message::*;
+pub use mention::*;
 pub use message_builder::*;
+pub use poll::*;
+pub use proxy::*;
+pub use response::*;
+pub use router::*;
+pub use sent_message::*;
+pub use snowflake::*;
+pub use storage::*;
+pub use table::*;
+pub use template::*;
+pub use timestamp::*;
+pub use webhook_url::*;
+pub use worker::*;
 
+pub mod alert;
+pub mod attachment;
+#[cfg(feature = "chart")]
+pub mod chart;
+pub mod client;
 pub mod color;
+pub mod component;
+pub mod container_builder;
+pub mod config;
+pub mod crash;
+pub mod device;
 pub mod edit;
 pub mod edit_builder;
 pub mod embed;
 pub mod embed_builder;
+pub mod emoji;
+pub mod flags;
+pub mod group;
+pub mod heartbeat;
+pub mod into_embed;
+pub mod layout;
+pub mod live_message;
+pub mod logger;
+pub mod markdown;
 pub mod message;
+pub mod mention;
 pub mod message_builder;
+pub mod poll;
+pub mod proxy;
+pub mod response;
+pub mod router;
+pub mod sent_message;
+pub mod snowflake;
+pub mod storage;
+pub mod table;
+pub mod template;
+pub mod timestamp;
+pub mod webhook_url;
+pub mod worker;
 
-use log::{error, info};
+mod oneshot;
+#[cfg(feature = "chart")]
+mod png;
+mod raw_http;
+mod sha256;
+mod stream;
+mod util;
 
-use embedded_svc::{http::client::Client as HttpClient, io::Write, utils::io};
-use esp_idf_svc::http::{client::{Configuration, EspHttpConnection}, Method};
+use log::log;
+
+use embedded_svc::{http::client::Client as HttpClient, io::Write};
+use serde::Serialize;
+use proxy::RawRequest;
+use stream::{Body, EmbeddedIo};
+use esp_idf_svc::http::{client::EspHttpConnection, Method};
 
 #[derive(Clone)]
 pub struct WebhookBuilder {
-    url: String,
+    /// The parsed url, or why parsing failed, which is reported when sending.
+    url: Result<WebhookUrl, String>,
+    config: WebhookConfig,
+    response: ResponseConfig,
 }
 
 impl WebhookBuilder {
+    /// Constructs a webhook from the url copied out of the discord client.
+    ///
+    /// An invalid `url` is reported as an error by every request, use [WebhookBuilder::try_new] to check it up front.
     pub fn new(url: impl Into<String>) -> Self {
-        Self { url: url.into() }
+        let url = url.into();
+        Self {
+            url: WebhookUrl::parse(&url).map_err(|e| e.to_string()),
+            config: WebhookConfig::new(),
+            response: ResponseConfig::new(),
+        }
+    }
+
+    /// Constructs a webhook from the url copied out of the discord client, failing if it is not a valid webhook url.
+    pub fn try_new(url: impl Into<String>) -> anyhow::Result<Self> {
+        Ok(Self::from_url(WebhookUrl::parse(&url.into())?))
+    }
+
+    /// Constructs a webhook from an already parsed [WebhookUrl].
+    pub fn from_url(url: WebhookUrl) -> Self {
+        Self {
+            url: Ok(url),
+            config: WebhookConfig::new(),
+            response: ResponseConfig::new(),
+        }
+    }
+
+    /// Sends requests to `api_base` instead of discord, for example a local mock server or a relay proxy.
+    ///
+    /// `api_base` replaces everything before `/webhooks/{id}/{token}`, so the id and token of the webhook url are kept.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use diswh_esp::WebhookBuilder;
+    ///
+    /// let webhook = WebhookBuilder::new("https://discord.com/api/webhooks/1234/token")
+    ///     .with_api_base("http://192.168.1.20:8080/api");
+    /// ```
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        self.url = self.url.map(|url| url.with_api_base(api_base));
+        self
+    }
+
+    /// Pins requests to a specific discord api version, such as `10` for `/api/v10/`.
+    ///
+    /// Overrides any version that was part of the webhook url, `None` uses discord's default.
+    pub fn with_api_version(mut self, api_version: Option<u8>) -> Self {
+        self.url = self.url.map(|url| url.with_api_version(api_version));
+        self
+    }
+
+    /// Posts messages into a thread of the channel instead of the channel itself.
+    ///
+    /// Sent as the `thread_id` query parameter, replacing one that was part of the webhook url.
+    pub fn with_thread_id(mut self, thread_id: impl Into<Snowflake>) -> Self {
+        let thread_id = thread_id.into().to_string();
+        self.url = self.url.map(|url| url.with_query("thread_id", thread_id));
+        self
+    }
+
+    /// Returns the url this webhook posts to, or why the url given to [WebhookBuilder::new] is invalid.
+    pub fn url(&self) -> anyhow::Result<&WebhookUrl> {
+        self.url
+            .as_ref()
+            .map_err(|e| anyhow::anyhow!("invalid discord webhook url: {}", e))
+    }
+
+    /// Sets the timeouts, buffer sizes and TLS settings used for requests.
+    ///
+    /// See [WebhookConfig] for the defaults.
+    pub fn with_config(mut self, config: WebhookConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets how responses from discord are read and logged.
+    ///
+    /// See [ResponseConfig] for the defaults.
+    pub fn with_response_config(mut self, response: ResponseConfig) -> Self {
+        self.response = response;
+        self
     }
 
     pub fn send_message(self, packet: MessagePacket) -> anyhow::Result<Self> {
-        WebhookBuilder::send_packet(&self.url.clone(), false, &packet.serialize_packet())?;
+        self.send_message_with_response(&packet)?;
         Ok(self)
     }
 
-    pub fn edit_message(self, packet: EditMessagePacket, id: usize) -> anyhow::Result<Self> {
-        WebhookBuilder::send_packet(
-            &(self.url.clone() + &format!("/messages/{}", id)),
-            true,
-            &packet.serialize_packet(),
-        )?;
+    pub fn edit_message(self, packet: EditMessagePacket, id: impl Into<Snowflake>) -> anyhow::Result<Self> {
+        self.edit_message_with_response(&packet, id)?;
         Ok(self)
     }
 
-    fn send_packet(url: &str, patch: bool, packet: &str) -> anyhow::Result<()> {
-        let mut client = HttpClient::wrap(EspHttpConnection::new(
-            &Configuration {
-                use_global_ca_store: true,
-                crt_bundle_attach: Some(esp_idf_svc::sys::esp_crt_bundle_attach),
-                ..Default::default()
-            }
-        )?);
+    /// Sends a message and returns the response discord sent back.
+    ///
+    /// A non 2xx status is not treated as an error, check [WebhookResponse::is_success] and the body to find out what went wrong.
+    pub fn send_message_with_response(&self, packet: &MessagePacket) -> anyhow::Result<WebhookResponse> {
+        self.send_message_on(&mut None, packet)
+    }
 
+    /// Like [WebhookBuilder::send_message_with_response], reusing `connection` if it is open and leaving it open for
+    /// the next request.
+    pub(crate) fn send_message_on(
+        &self,
+        connection: &mut Option<EspHttpConnection>,
+        packet: &MessagePacket,
+    ) -> anyhow::Result<WebhookResponse> {
+        packet.validate()?;
+        let query = components_query(&packet.components, &[]);
+        let body = Body::new(packet, &packet.files)?;
+        self.send_packet(connection, Method::Post, "", &query, Some(body), self.response.body)
+    }
 
-        let headers = [("Content-Type", "application/json")];
-        let method = if patch { Method::Patch } else { Method::Post };
+    /// Sends a message and waits for discord to return it, so its id can be used to edit it later.
+    ///
+    /// Unlike [WebhookBuilder::send_message_with_response] the whole body is always read, and a non 2xx status is an error.
+    pub fn send_message_and_wait(&self, packet: &MessagePacket) -> anyhow::Result<SentMessage> {
+        let response = self.send_message_waiting(packet)?;
+        if !response.is_success() {
+            anyhow::bail!("discord responded with status {}", response.status);
+        }
+        Ok(serde_json::from_slice(&response.body)?)
+    }
 
-        let mut request = client.request(method, url, &headers)?;
-        request.write_all(packet.as_bytes())?;
-        request.flush()?;
-        if patch {
-            info!("-> PATCH {}", url);
-        } else {
-            info!("-> POST {}", url);
+    /// Sends a message with `wait=true` and returns the whole response, which holds the sent message on success.
+    pub(crate) fn send_message_waiting(&self, packet: &MessagePacket) -> anyhow::Result<WebhookResponse> {
+        packet.validate()?;
+        let query = components_query(&packet.components, &[("wait", "true")]);
+        let body = Body::new(packet, &packet.files)?;
+        self.send_packet(&mut None, Method::Post, "", &query, Some(body), ResponseBody::Full)
+    }
+
+    /// Edits a previously sent message and returns the response discord sent back.
+    ///
+    /// A non 2xx status is not treated as an error, check [WebhookResponse::is_success] and the body to find out what went wrong.
+    pub fn edit_message_with_response(&self, packet: &EditMessagePacket, id: impl Into<Snowflake>) -> anyhow::Result<WebhookResponse> {
+        self.edit_message_on(&mut None, packet, id.into())
+    }
+
+    /// Like [WebhookBuilder::edit_message_with_response], reusing `connection` if it is open and leaving it open for
+    /// the next request.
+    pub(crate) fn edit_message_on(
+        &self,
+        connection: &mut Option<EspHttpConnection>,
+        packet: &EditMessagePacket,
+        id: Snowflake,
+    ) -> anyhow::Result<WebhookResponse> {
+        packet.validate()?;
+        let query = components_query(packet.components.as_deref().unwrap_or_default(), &[]);
+        let path = format!("/messages/{}", id);
+        self.send_packet(connection, Method::Patch, &path, &query, Some(Body::new(packet, &[])?), self.response.body)
+    }
+
+    /// Fetches a message previously sent by this webhook, for example to read the results of a poll.
+    ///
+    /// A non 2xx status is an error.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use diswh_esp::WebhookBuilder;
+    ///
+    /// let webhook = WebhookBuilder::new("https://discord.com/api/webhooks/1234/token");
+    /// let message = webhook.get_message(1098765432109876543u64)?;
+    /// if let Some(poll) = message.poll {
+    ///     println!("{} operators acknowledged", poll.votes("Yes"));
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn get_message(&self, id: impl Into<Snowflake>) -> anyhow::Result<SentMessage> {
+        let response = self.send_packet(
+            &mut None,
+            Method::Get,
+            &format!("/messages/{}", id.into()),
+            &[],
+            None::<Body<()>>,
+            ResponseBody::Full,
+        )?;
+        if !response.is_success() {
+            anyhow::bail!("discord responded with status {}", response.status);
+        }
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+
+    fn send_packet<T: Serialize>(
+        &self,
+        connection: &mut Option<EspHttpConnection>,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<Body<T>>,
+        response_body: ResponseBody,
+    ) -> anyhow::Result<WebhookResponse> {
+        // Serialize twice rather than buffer, once to learn the length and once straight into the connection.
+        let content = match &body {
+            Some(body) => Some((body.content_type(), body.content_length()?.to_string())),
+            None => None,
+        };
+        let headers = match &content {
+            Some((content_type, content_length)) => vec![
+                ("Content-Type", content_type.as_str()),
+                ("Content-Length", content_length.as_str()),
+            ],
+            None => Vec::new(),
+        };
+        let webhook_url = self.url()?;
+        let url = webhook_url.endpoint(path, query);
+        let secret = if self.response.redact { webhook_url.token() } else { "" };
+        if let Some(level) = self.response.log_level {
+            log!(level, "-> {} {}", format!("{:?}", method).to_uppercase(), response::redact(&url, secret));
         }
-        let mut response = request.submit()?;
+
+        let (status, body, truncated) = if self.config.needs_raw_connection() {
+            self.send_raw(method, &url, &headers, body.as_ref(), response_body)?
+        } else {
+            self.send_direct(connection, method, &url, &headers, body.as_ref(), response_body)?
+        };
 
         // Process response
-        let status = response.status();
-        info!("<- {}", status);
-        let mut buf = [0u8; 1024];
-        let bytes_read = io::try_read_full(&mut response, &mut buf).map_err(|e| e.0)?;
-        info!("Read {} bytes", bytes_read);
-        match std::str::from_utf8(&buf[0..bytes_read]) {
-            Ok(body_string) => info!(
-                "Response body (truncated to {} bytes): {:?}",
-                buf.len(),
-                body_string
-            ),
-            Err(e) => error!("Error decoding response body: {}", e),
+        if let Some(level) = self.response.log_level {
+            log!(level, "<- {}", status);
+        }
+        if let (Some(level), false) = (self.response.log_level, response_body == ResponseBody::Discard) {
+            match std::str::from_utf8(&body) {
+                Ok(body_string) if truncated => log!(
+                    level,
+                    "Response body (truncated to {} bytes): {:?}",
+                    body.len(),
+                    response::redact(body_string, secret)
+                ),
+                Ok(body_string) => log!(level, "Response body: {:?}", response::redact(body_string, secret)),
+                Err(e) => log!(level, "Error decoding response body: {}", e),
+            };
+        }
+
+        Ok(WebhookResponse { status, body, truncated })
+    }
+
+    /// Sends a request with the ESP http client, returning the status, body and whether the body was truncated.
+    ///
+    /// Opens `connection` if it is not open yet, and closes it again if the request fails half way through.
+    fn send_direct<T: Serialize>(
+        &self,
+        connection: &mut Option<EspHttpConnection>,
+        method: Method,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&Body<T>>,
+        response_body: ResponseBody,
+    ) -> anyhow::Result<(u16, Vec<u8>, bool)> {
+        let open = match connection {
+            Some(open) => open,
+            None => connection.insert(EspHttpConnection::new(&self.config.http_configuration()?)?),
         };
+        let result = (|| {
+            let mut client = HttpClient::wrap(open);
+            let mut request = client.request(method, url, headers)?;
+            if let Some(body) = body {
+                body.write(EmbeddedIo(&mut request))?;
+            }
+            request.flush()?;
+            let mut response = request.submit()?;
+
+            let status = response.status();
+            let (body, truncated) = response::read_body(EmbeddedIo(&mut response), response_body)?;
+            // Read what is left of the body, so the connection is ready for the next request.
+            std::io::copy(&mut EmbeddedIo(&mut response), &mut std::io::sink())?;
+            Ok((status, body, truncated))
+        })();
+        if result.is_err() {
+            *connection = None;
+        }
+        result
+    }
+
+    /// Sends a request over a connection opened by hand, returning the status, body and whether the body was truncated.
+    fn send_raw<T: Serialize>(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: Option<&Body<T>>,
+        response_body: ResponseBody,
+    ) -> anyhow::Result<(u16, Vec<u8>, bool)> {
+        let mut request = RawRequest::open(self.config.proxy.as_ref(), &self.config, method, url, headers)?;
+        if let Some(body) = body {
+            body.write(&mut request)?;
+        }
+        let (status, reader) = request.submit()?;
+        let (body, truncated) = response::read_body(reader, response_body)?;
+        Ok((status, body, truncated))
+    }
+}
 
-        client.release();
+/// Adds `with_components=true` to `query` when there are components, otherwise discord drops them from messages sent
+/// by webhooks that are not owned by an application.
+fn components_query<'a>(components: &[Component], query: &[(&'a str, &'a str)]) -> Vec<(&'a str, &'a str)> {
+    let mut query = query.to_vec();
+    if !components.is_empty() {
+        query.push(("with_components", "true"));
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_defers_invalid_urls_to_requests() {
+        let webhook = WebhookBuilder::new("https://example.com/not-a-webhook").with_thread_id(5);
+        let error = webhook.url().unwrap_err().to_string();
+        assert!(error.starts_with("invalid discord webhook url"), "{}", error);
+        assert!(WebhookBuilder::try_new("https://example.com/not-a-webhook").is_err());
+    }
 
-        Ok(())
+    #[test]
+    fn new_keeps_valid_urls() {
+        let webhook = WebhookBuilder::new("https://discord.com/api/webhooks/1234/token").with_thread_id(5);
+        assert_eq!(webhook.url().unwrap().query("thread_id"), Some("5"));
     }
 }
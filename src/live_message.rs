@@ -0,0 +1,296 @@
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use log::warn;
+
+use super::{EditMessagePacket, SentMessage, Snowflake, Storage, WebhookBuilder, WebhookResponse};
+
+/// A message that is posted once and then kept up to date by editing it, such as a status embed.
+///
+/// The first update posts the message and remembers its id, later updates edit it. If the message was deleted in the
+/// meantime it is posted again. Updates arriving faster than the minimum interval are skipped, as are updates while
+/// discord is rate limiting the webhook.
+///
+/// With [LiveMessage::with_storage] the id survives reboots, so the device keeps editing the same message.
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use diswh_esp::{EditMessageBuilder, EmbedBuilder, LiveMessage, WebhookBuilder};
+///
+/// let mut status = LiveMessage::new(WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"))
+///     .with_min_interval(Duration::from_secs(30));
+///
+/// loop {
+///     let embed = EmbedBuilder::new()
+///         .with_title("Greenhouse")
+///         .add_field("Temperature", "21.4 °C", true)
+///         .build();
+///     status.update(EditMessageBuilder::new("").add_embed(embed).build())?;
+///     std::thread::sleep(Duration::from_secs(60));
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct LiveMessage {
+    webhook: WebhookBuilder,
+    message_id: Option<Snowflake>,
+    min_interval: Duration,
+    last_update: Option<Instant>,
+    blocked_until: Option<Instant>,
+    storage: Option<(Box<dyn Storage + Send>, String)>,
+    loaded: bool,
+}
+
+impl LiveMessage {
+    /// Constructs a live message that has not been posted yet.
+    pub fn new(webhook: WebhookBuilder) -> Self {
+        Self {
+            webhook,
+            message_id: None,
+            min_interval: Duration::from_secs(5),
+            last_update: None,
+            blocked_until: None,
+            storage: None,
+            loaded: false,
+        }
+    }
+
+    /// Sets the minimum time between two updates, defaults to 5 seconds.
+    pub fn with_min_interval(mut self, min_interval: Duration) -> Self {
+        self.min_interval = min_interval;
+        self
+    }
+
+    /// Keeps the message id in `storage` under `key`, so the same message is edited after a reboot.
+    ///
+    /// # Panics
+    /// Will panic if the provided `key` can not be converted into a [String]
+    pub fn with_storage(mut self, storage: impl Storage + Send + 'static, key: impl Into<String>) -> Self {
+        self.storage = Some((Box::new(storage), key.into()));
+        self.loaded = false;
+        self
+    }
+
+    /// Continues editing an already posted message.
+    pub fn with_message_id(mut self, id: impl Into<Snowflake>) -> Self {
+        self.message_id = Some(id.into());
+        self.loaded = true;
+        self
+    }
+
+    /// The id of the message, once it has been posted.
+    pub fn message_id(&self) -> Option<Snowflake> {
+        self.message_id
+    }
+
+    /// Updates the message, unless the last successful update was less than the minimum interval ago.
+    ///
+    /// Returns whether the update was sent.
+    pub fn update(&mut self, packet: EditMessagePacket) -> anyhow::Result<bool> {
+        if !self.is_due(Instant::now()) {
+            return Ok(false);
+        }
+        self.force_update(packet)
+    }
+
+    /// Updates the message right away, ignoring the minimum interval.
+    ///
+    /// Still skips the update while discord is rate limiting the webhook, returning whether it was sent.
+    pub fn force_update(&mut self, packet: EditMessagePacket) -> anyhow::Result<bool> {
+        if self.is_blocked(Instant::now()) {
+            return Ok(false);
+        }
+        self.load_id()?;
+
+        if let Some(id) = self.message_id {
+            let response = self.webhook.edit_message_with_response(&packet, id)?;
+            if let Some(sent) = self.edited(&response, Instant::now())? {
+                return Ok(sent);
+            }
+        }
+        let response = self.webhook.send_message_waiting(&packet.into())?;
+        self.posted(&response, Instant::now())
+    }
+
+    /// Returns true when an update is neither throttled by the minimum interval nor blocked by a rate limit.
+    fn is_due(&self, now: Instant) -> bool {
+        let throttled = self
+            .last_update
+            .is_some_and(|last| now.saturating_duration_since(last) < self.min_interval);
+        !throttled && !self.is_blocked(now)
+    }
+
+    fn is_blocked(&self, now: Instant) -> bool {
+        self.blocked_until.is_some_and(|until| now < until)
+    }
+
+    /// Handles the response to an edit, returning whether it was sent, or `None` if the message has to be posted again.
+    fn edited(&mut self, response: &WebhookResponse, now: Instant) -> anyhow::Result<Option<bool>> {
+        match response.status {
+            status if (200..300).contains(&status) => {
+                self.last_update = Some(now);
+                Ok(Some(true))
+            }
+            404 => {
+                if let Some(id) = self.message_id {
+                    warn!("Live message {} was deleted, posting it again", id);
+                }
+                Ok(None)
+            }
+            429 => {
+                self.rate_limited(response, now);
+                Ok(Some(false))
+            }
+            status => bail!("editing live message failed with status {}", status),
+        }
+    }
+
+    /// Handles the response to posting the message, remembering its id, and returns whether it was sent.
+    fn posted(&mut self, response: &WebhookResponse, now: Instant) -> anyhow::Result<bool> {
+        match response.status {
+            status if (200..300).contains(&status) => {}
+            429 => {
+                self.rate_limited(response, now);
+                return Ok(false);
+            }
+            status => bail!("posting live message failed with status {}", status),
+        }
+        let message: SentMessage = serde_json::from_slice(&response.body)?;
+        self.message_id = Some(message.id);
+        self.last_update = Some(now);
+        if let Some((storage, key)) = &mut self.storage {
+            storage.store(key, &message.id.get().to_le_bytes())?;
+        }
+        Ok(true)
+    }
+
+    /// Forgets the message, the next update posts a new one.
+    pub fn forget(&mut self) -> anyhow::Result<()> {
+        self.message_id = None;
+        self.loaded = true;
+        if let Some((storage, key)) = &mut self.storage {
+            storage.remove(key)?;
+        }
+        Ok(())
+    }
+
+    fn load_id(&mut self) -> anyhow::Result<()> {
+        if self.loaded {
+            return Ok(());
+        }
+        if let Some((storage, key)) = &mut self.storage {
+            if let Some(bytes) = storage.load(key)? {
+                if let Ok(bytes) = <[u8; 8]>::try_from(bytes.as_slice()) {
+                    self.message_id = Some(Snowflake::new(u64::from_le_bytes(bytes)));
+                }
+            }
+        }
+        self.loaded = true;
+        Ok(())
+    }
+
+    fn rate_limited(&mut self, response: &WebhookResponse, now: Instant) {
+        let retry_after = response.retry_after().unwrap_or(self.min_interval);
+        warn!("Live message rate limited, retrying in {:.1}s", retry_after.as_secs_f64());
+        self.blocked_until = Some(now + retry_after);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStorage;
+
+    fn live_message() -> LiveMessage {
+        LiveMessage::new(WebhookBuilder::new("https://discord.com/api/webhooks/1/t"))
+            .with_min_interval(Duration::from_secs(30))
+    }
+
+    fn response(status: u16, body: &str) -> WebhookResponse {
+        WebhookResponse {
+            status,
+            body: body.as_bytes().to_vec(),
+            truncated: false,
+        }
+    }
+
+    fn stored(live: &mut LiveMessage) -> Option<Vec<u8>> {
+        let (storage, key) = live.storage.as_mut().unwrap();
+        storage.load(key).unwrap()
+    }
+
+    #[test]
+    fn stores_the_posted_id_and_loads_it_after_a_reboot() {
+        let mut live = live_message().with_storage(MemoryStorage::new(), "status");
+        live.load_id().unwrap();
+        assert_eq!(live.message_id(), None);
+
+        assert!(live.posted(&response(200, r#"{"id":"1234567890123"}"#), Instant::now()).unwrap());
+        assert_eq!(live.message_id(), Some(Snowflake::new(1234567890123)));
+        let bytes = stored(&mut live).unwrap();
+        assert_eq!(bytes, 1234567890123u64.to_le_bytes());
+
+        let mut storage = MemoryStorage::new();
+        storage.store("status", &bytes).unwrap();
+        let mut rebooted = live_message().with_storage(storage, "status");
+        rebooted.load_id().unwrap();
+        assert_eq!(rebooted.message_id(), Some(Snowflake::new(1234567890123)));
+
+        rebooted.forget().unwrap();
+        assert_eq!(rebooted.message_id(), None);
+        assert_eq!(stored(&mut rebooted), None);
+    }
+
+    #[test]
+    fn ignores_stored_ids_of_the_wrong_length() {
+        let mut storage = MemoryStorage::new();
+        storage.store("status", &[1, 2, 3]).unwrap();
+        let mut live = live_message().with_storage(storage, "status");
+        live.load_id().unwrap();
+        assert_eq!(live.message_id(), None);
+    }
+
+    #[test]
+    fn throttles_only_after_successful_updates() {
+        let mut live = live_message();
+        let start = Instant::now();
+        assert!(live.is_due(start));
+
+        assert!(live.edited(&response(500, ""), start).is_err());
+        assert!(live.is_due(start), "a failed update does not throttle");
+
+        assert_eq!(live.edited(&response(204, ""), start).unwrap(), Some(true));
+        assert!(!live.is_due(start + Duration::from_secs(29)));
+        assert!(live.is_due(start + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn reposts_deleted_messages() {
+        let mut live = live_message().with_message_id(42u64);
+        let now = Instant::now();
+        assert_eq!(live.edited(&response(404, ""), now).unwrap(), None);
+        assert!(live.is_due(now));
+
+        assert!(live.posted(&response(200, r#"{"id":"43"}"#), now).unwrap());
+        assert_eq!(live.message_id(), Some(Snowflake::new(43)));
+        assert!(live.posted(&response(400, ""), now).is_err());
+        assert!(live.posted(&response(200, "not json"), now).is_err());
+    }
+
+    #[test]
+    fn backs_off_while_rate_limited() {
+        let mut live = live_message();
+        let now = Instant::now();
+        assert_eq!(live.edited(&response(429, r#"{"retry_after":2.5}"#), now).unwrap(), Some(false));
+        assert!(live.is_blocked(now + Duration::from_secs(2)));
+        assert!(!live.is_due(now + Duration::from_secs(2)));
+        assert!(live.is_due(now + Duration::from_millis(2500)));
+
+        // Without a usable retry_after the minimum interval is waited, for reposts as well.
+        assert!(!live.posted(&response(429, "{}"), now).unwrap());
+        assert!(live.is_blocked(now + Duration::from_secs(29)));
+        assert!(!live.is_blocked(now + Duration::from_secs(30)));
+        assert_eq!(live.message_id(), None);
+    }
+}
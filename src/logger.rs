@@ -0,0 +1,401 @@
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use super::{
+    markdown, message::CONTENT_LIMIT, util::truncate, Color, EmbedBuilder, MessageBuilder, MessagePacket, WebhookBuilder,
+};
+
+/// The most characters discord accepts in an embed's description.
+const DESCRIPTION_LIMIT: usize = 4096;
+/// The most embeds discord accepts on a single message.
+const EMBED_LIMIT: usize = 10;
+/// The most characters discord accepts across all embeds of a message.
+const EMBED_TOTAL_LIMIT: usize = 6000;
+/// The pause between the messages of one batch, which keeps them under discord's limit of 5 requests per 2 seconds.
+const PACKET_SPACING: Duration = Duration::from_millis(500);
+/// How often a message is sent again after discord rate limited it, before it is given up on.
+const RATE_LIMIT_RETRIES: usize = 3;
+
+thread_local! {
+    /// Set on the sender thread, so the logs of its own http traffic are not sent to discord again.
+    static IS_SENDER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// How a batch of log records is laid out in discord.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One message per batch, with the records as lines of a code block.
+    CodeBlock,
+    /// One embed per record, colored by level.
+    Embeds,
+}
+
+struct LogLine {
+    level: Level,
+    target: String,
+    message: String,
+}
+
+struct LogQueue {
+    lines: VecDeque<LogLine>,
+    dropped: usize,
+    flush: bool,
+}
+
+struct Shared {
+    queue: Mutex<LogQueue>,
+    wake: Condvar,
+}
+
+/// A [log] backend that ships records to a discord channel.
+///
+/// Records at or above the configured level are buffered and sent in batches from a background thread, at most once
+/// per flush interval. When records come in faster than they can be sent the oldest are dropped, and the next batch
+/// says how many. Everything is also passed on to an inner logger, such as the ESP console logger, if one is set.
+///
+/// Records logged by the background thread itself, such as its own http traffic and failures to send, only reach the
+/// inner logger, so sending logs never produces more logs to send.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{DiscordLogger, LogFormat, WebhookBuilder};
+///
+/// DiscordLogger::new(WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"))
+///     .with_level(log::LevelFilter::Warn)
+///     .with_format(LogFormat::Embeds)
+///     .init()?;
+///
+/// log::warn!("Battery at 5%");
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct DiscordLogger {
+    webhook: WebhookBuilder,
+    level: LevelFilter,
+    format: LogFormat,
+    flush_interval: Duration,
+    capacity: usize,
+    inner: Option<Box<dyn Log>>,
+    shared: Arc<Shared>,
+}
+
+impl DiscordLogger {
+    /// Constructs a logger sending warnings and errors every 10 seconds, buffering up to 50 records.
+    pub fn new(webhook: WebhookBuilder) -> Self {
+        Self {
+            webhook,
+            level: LevelFilter::Warn,
+            format: LogFormat::Embeds,
+            flush_interval: Duration::from_secs(10),
+            capacity: 50,
+            inner: None,
+            shared: Arc::new(Shared {
+                queue: Mutex::new(LogQueue {
+                    lines: VecDeque::new(),
+                    dropped: 0,
+                    flush: false,
+                }),
+                wake: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Sets the lowest level that is sent to discord.
+    pub fn with_level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Sets how records are laid out in discord.
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Sets how often buffered records are sent, which is also the most often the webhook is used.
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = flush_interval;
+        self
+    }
+
+    /// Sets how many records are buffered before the oldest are dropped.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Passes every record on to `logger` as well, such as `esp_idf_svc::log::EspLogger`.
+    pub fn with_inner(mut self, logger: impl Log + 'static) -> Self {
+        self.inner = Some(Box::new(logger));
+        self
+    }
+
+    /// Installs this as the global logger and starts the sender thread.
+    ///
+    /// Fails if a global logger was already installed, no thread is started then. Should the thread fail to start the
+    /// logger stays installed, still passing records to the inner logger, but nothing is sent to discord.
+    pub fn init(self) -> anyhow::Result<()> {
+        let max_level = if self.inner.is_some() {
+            LevelFilter::Trace
+        } else {
+            self.level
+        };
+        let webhook = self.webhook.clone();
+        let shared = self.shared.clone();
+        let format = self.format;
+        let flush_interval = self.flush_interval;
+        log::set_boxed_logger(Box::new(self)).map_err(|e| anyhow::anyhow!("{}", e))?;
+        log::set_max_level(max_level);
+        thread::Builder::new()
+            .name("diswh-logger".into())
+            .stack_size(super::client::DEFAULT_STACK_SIZE)
+            .spawn(move || run(webhook, shared, format, flush_interval))?;
+        Ok(())
+    }
+
+    fn forwards(&self, record: &Record) -> bool {
+        record.level() <= self.level && !IS_SENDER.with(|is_sender| is_sender.get())
+    }
+}
+
+impl Log for DiscordLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level || self.inner.as_ref().is_some_and(|inner| inner.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        if let Some(inner) = &self.inner {
+            inner.log(record);
+        }
+        if !self.forwards(record) {
+            return;
+        }
+        let mut queue = self.shared.queue.lock().unwrap();
+        if queue.lines.len() >= self.capacity {
+            queue.lines.pop_front();
+            queue.dropped += 1;
+        }
+        queue.lines.push_back(LogLine {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        });
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = &self.inner {
+            inner.flush();
+        }
+        self.shared.queue.lock().unwrap().flush = true;
+        self.shared.wake.notify_all();
+    }
+}
+
+fn run(webhook: WebhookBuilder, shared: Arc<Shared>, format: LogFormat, flush_interval: Duration) {
+    IS_SENDER.with(|is_sender| is_sender.set(true));
+    let mut last_send = Instant::now();
+    loop {
+        let (lines, dropped) = {
+            let mut queue = shared.queue.lock().unwrap();
+            while !queue.flush && last_send.elapsed() < flush_interval {
+                let remaining = flush_interval.saturating_sub(last_send.elapsed());
+                queue = shared.wake.wait_timeout(queue, remaining).unwrap().0;
+            }
+            queue.flush = false;
+            let dropped = std::mem::take(&mut queue.dropped);
+            (queue.lines.drain(..).collect::<Vec<_>>(), dropped)
+        };
+        last_send = Instant::now();
+        if lines.is_empty() && dropped == 0 {
+            continue;
+        }
+        let packets = match format {
+            LogFormat::CodeBlock => code_block_packets(&lines, dropped),
+            LogFormat::Embeds => embed_packets(&lines, dropped),
+        };
+        for (index, packet) in packets.iter().enumerate() {
+            if index > 0 {
+                thread::sleep(PACKET_SPACING);
+            }
+            send(&webhook, packet);
+        }
+    }
+}
+
+/// Sends one message of a batch, waiting out rate limits. Failures are logged, which only reaches the inner logger.
+fn send(webhook: &WebhookBuilder, packet: &MessagePacket) {
+    for _ in 0..=RATE_LIMIT_RETRIES {
+        let response = match webhook.send_message_with_response(packet) {
+            Ok(response) => response,
+            Err(e) => {
+                log::error!("Failed to send logs to discord: {}", e);
+                return;
+            }
+        };
+        if response.status != 429 {
+            if !response.is_success() {
+                log::error!("Failed to send logs to discord, status {}", response.status);
+            }
+            return;
+        }
+        // Without a usable retry_after, back off for a second like discord's usual per-webhook limit.
+        let retry_after = response.retry_after().unwrap_or(Duration::from_secs(1));
+        log::warn!("Logs rate limited, retrying in {:.1}s", retry_after.as_secs_f64());
+        thread::sleep(retry_after);
+    }
+    log::error!("Dropped logs after being rate limited {} times", RATE_LIMIT_RETRIES + 1);
+}
+
+fn code_block_packets(lines: &[LogLine], dropped: usize) -> Vec<MessagePacket> {
+    // Leave room for the code fence around the lines.
+    let limit = CONTENT_LIMIT - 8;
+    let mut packets = Vec::new();
+    let mut block = String::new();
+    if dropped > 0 {
+        block += &format!("({} records dropped)\n", dropped);
+    }
+    for line in lines {
+        let text = format!("[{:<5} {}] {}\n", line.level, line.target, markdown::escape_code_block(&line.message));
+        let text = truncate(&text, limit);
+        if block.len() + text.len() > limit {
+            packets.push(MessageBuilder::new(format!("```\n{}```", block), false).build());
+            block.clear();
+        }
+        block += text;
+    }
+    if !block.is_empty() {
+        packets.push(MessageBuilder::new(format!("```\n{}```", block), false).build());
+    }
+    packets
+}
+
+fn embed_packets(lines: &[LogLine], dropped: usize) -> Vec<MessagePacket> {
+    let mut packets = Vec::new();
+    let content = if dropped > 0 {
+        format!("({} records dropped)", dropped)
+    } else {
+        String::new()
+    };
+    let mut message = MessageBuilder::new(content, false);
+    let mut embeds = 0;
+    let mut total = 0;
+    for line in lines {
+        let title = format!("{} {}", line.level, line.target);
+        let description = truncate(&line.message, DESCRIPTION_LIMIT);
+        let size = title.len() + description.len();
+        if embeds == EMBED_LIMIT || total + size > EMBED_TOTAL_LIMIT {
+            packets.push(message.build());
+            message = MessageBuilder::new("", false);
+            embeds = 0;
+            total = 0;
+        }
+        message = message.add_embed(
+            EmbedBuilder::new()
+                .with_title(title)
+                .with_description(description)
+                .with_color(level_color(line.level))
+                .build(),
+        );
+        embeds += 1;
+        total += size;
+    }
+    packets.push(message.build());
+    packets
+}
+
+fn level_color(level: Level) -> i32 {
+    match level {
+        Level::Error => Color::RED,
+        Level::Warn => Color::GOLD,
+        Level::Info => Color::BLUE,
+        Level::Debug => Color::GREY,
+        Level::Trace => Color::DARK_GREY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(level: Level, message: &str) -> LogLine {
+        LogLine {
+            level,
+            target: "boiler".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn code_blocks_hold_escaped_lines() {
+        let lines = [line(Level::Warn, "Pressure high"), line(Level::Error, "```rm -rf```")];
+        let packets = code_block_packets(&lines, 3);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(
+            packets[0].content,
+            concat!(
+                "```\n(3 records dropped)\n[WARN  boiler] Pressure high\n",
+                "[ERROR boiler] `\u{200b}`\u{200b}`rm -rf`\u{200b}`\u{200b}`\n```",
+            )
+        );
+        assert_eq!(code_block_packets(&[], 2)[0].content, "```\n(2 records dropped)\n```");
+        assert!(code_block_packets(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn code_blocks_split_at_the_content_limit() {
+        let lines: Vec<_> = (0..100).map(|i| line(Level::Warn, &format!("{:02} {}", i, "x".repeat(80)))).collect();
+        let packets = code_block_packets(&lines, 0);
+        assert!(packets.len() > 1);
+        for packet in &packets {
+            assert!(packet.content.chars().count() <= CONTENT_LIMIT, "{}", packet.content.len());
+            assert!(packet.content.starts_with("```\n[WARN  boiler] "));
+            assert!(packet.content.ends_with("\n```"));
+        }
+        let joined: String = packets.iter().map(|packet| packet.content.as_str()).collect();
+        assert_eq!(joined.matches("[WARN  boiler]").count(), 100);
+
+        // A single record longer than a message is cut short instead of overflowing it.
+        let packets = code_block_packets(&[line(Level::Error, &"y".repeat(5000))], 0);
+        assert_eq!(packets.len(), 1);
+        assert!(packets[0].content.len() <= CONTENT_LIMIT);
+        assert!(packets[0].content.len() > CONTENT_LIMIT - 10);
+    }
+
+    #[test]
+    fn embeds_are_colored_by_level() {
+        let lines = [line(Level::Error, "Pump stopped"), line(Level::Warn, "Pressure high")];
+        let packets = embed_packets(&lines, 2);
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].content, "(2 records dropped)");
+        let embeds = &packets[0].embeds;
+        assert_eq!(embeds.len(), 2);
+        assert_eq!(embeds[0].title.as_deref(), Some("ERROR boiler"));
+        assert_eq!(embeds[0].description.as_deref(), Some("Pump stopped"));
+        assert_eq!(embeds[0].color, Color::RED);
+        assert_eq!(embeds[1].title.as_deref(), Some("WARN boiler"));
+        assert_eq!(embeds[1].color, Color::GOLD);
+        assert!(embed_packets(&lines, 0)[0].content.is_empty());
+    }
+
+    #[test]
+    fn embeds_split_at_the_embed_and_size_limits() {
+        let lines: Vec<_> = (0..25).map(|i| line(Level::Warn, &format!("record {}", i))).collect();
+        let counts: Vec<_> = embed_packets(&lines, 0).iter().map(|packet| packet.embeds.len()).collect();
+        assert_eq!(counts, [10, 10, 5]);
+
+        // Each description is cut to 4096 characters, so only one fits under the 6000 character total.
+        let lines: Vec<_> = (0..3).map(|_| line(Level::Error, &"z".repeat(5000))).collect();
+        let packets = embed_packets(&lines, 0);
+        assert_eq!(packets.len(), 3);
+        for packet in &packets {
+            assert_eq!(packet.embeds.len(), 1);
+            assert_eq!(packet.embeds[0].description.as_ref().unwrap().len(), DESCRIPTION_LIMIT);
+        }
+    }
+}
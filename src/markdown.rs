@@ -0,0 +1,209 @@
+//! Helpers producing discord markdown, for message content, embed descriptions and field values.
+//!
+//! The formatting helpers wrap their input as is, so pass untrusted text, such as sensor names, through [escape] first.
+//!
+//! # Example
+//! ```
+//! use diswh_esp::markdown;
+//!
+//! let sensor = "boiler_1*";
+//! let content = format!("{} is {}", markdown::bold(markdown::escape(sensor)), markdown::italic("offline"));
+//! assert_eq!(content, r"**boiler\_1\*** is *offline*");
+//! ```
+
+use std::fmt::Display;
+
+/// Characters that format text wherever they appear.
+const INLINE_SPECIAL: &[char] = &['\\', '*', '_', '~', '`', '|', '[', ']', '(', ')', '<', '>'];
+/// Characters that only format text at the start of a line, as headers, quotes and lists.
+const LINE_START_SPECIAL: &[char] = &['#', '>', '-', '+'];
+
+/// Escapes discord markdown in `text`, so it shows exactly as written.
+///
+/// Formatting characters get a backslash in front of them, as do headers, quotes and lists at the start of a line.
+///
+/// # Example
+/// ```
+/// use diswh_esp::markdown;
+///
+/// assert_eq!(markdown::escape("# __init__ ~1 [x]"), r"\# \_\_init\_\_ \~1 \[x\]");
+/// assert_eq!(markdown::escape("1. first"), r"1\. first");
+/// assert_eq!(markdown::escape("72.5 °C"), "72.5 °C");
+/// assert_eq!(markdown::escape("> -5 °C"), r"\> -5 °C");
+/// ```
+pub fn escape(text: impl AsRef<str>) -> String {
+    let text = text.as_ref();
+    let mut escaped = String::with_capacity(text.len());
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            escaped.push('\n');
+        }
+        let indent = line.len() - line.trim_start().len();
+        escaped += &line[..indent];
+        let line = &line[indent..];
+        if line.starts_with(LINE_START_SPECIAL) && !line.starts_with(INLINE_SPECIAL) {
+            escaped.push('\\');
+        }
+        // Numbered lists, such as `1. first`, but not numbers such as `72.5`
+        let digits = line.chars().take_while(char::is_ascii_digit).count();
+        let numbered = digits > 0 && line[digits..].starts_with(". ");
+        for (position, c) in line.char_indices() {
+            if INLINE_SPECIAL.contains(&c) || (numbered && position == digits) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Makes code blocks safe to hold `text`, by breaking up triple backticks with a zero width space.
+pub fn escape_code_block(text: impl AsRef<str>) -> String {
+    text.as_ref().replace("```", "`\u{200b}`\u{200b}`")
+}
+
+/// **bold**
+pub fn bold(text: impl Display) -> String {
+    format!("**{}**", text)
+}
+
+/// *italic*
+pub fn italic(text: impl Display) -> String {
+    format!("*{}*", text)
+}
+
+/// underlined
+pub fn underline(text: impl Display) -> String {
+    format!("__{}__", text)
+}
+
+/// ~~struck through~~
+pub fn strikethrough(text: impl Display) -> String {
+    format!("~~{}~~", text)
+}
+
+/// Hidden until clicked.
+pub fn spoiler(text: impl Display) -> String {
+    format!("||{}||", text)
+}
+
+/// `inline code`, using double backticks when `text` holds a backtick itself.
+///
+/// # Example
+/// ```
+/// use diswh_esp::markdown;
+///
+/// assert_eq!(markdown::inline_code("a`b"), "`` a`b ``");
+/// ```
+pub fn inline_code(text: impl AsRef<str>) -> String {
+    let text = text.as_ref();
+    if text.contains('`') {
+        format!("`` {} ``", text.replace("``", "`\u{200b}`"))
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+/// A fenced code block, highlighted as `language` unless it is empty.
+///
+/// Triple backticks inside `text` are broken up, so they can not end the block early.
+///
+/// # Example
+/// ```
+/// use diswh_esp::markdown;
+///
+/// assert_eq!(markdown::code_block("json", r#"{"ok": true}"#), "```json\n{\"ok\": true}\n```");
+/// ```
+pub fn code_block(language: &str, text: impl AsRef<str>) -> String {
+    format!("```{}\n{}\n```", language, escape_code_block(text))
+}
+
+/// A header, `level` 1 being the largest.
+///
+/// # Panics
+/// Will panic if `level` is not 1, 2 or 3, the only levels discord supports.
+pub fn header(level: u8, text: impl Display) -> String {
+    assert!((1..=3).contains(&level), "discord supports header levels 1 to 3");
+    format!("{} {}", "#".repeat(level as usize), text)
+}
+
+/// Small, muted text below the rest of the message.
+pub fn subtext(text: impl Display) -> String {
+    format!("-# {}", text)
+}
+
+/// Quotes every line of `text`.
+pub fn quote(text: impl AsRef<str>) -> String {
+    text.as_ref()
+        .split('\n')
+        .map(|line| format!("> {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Quotes `text` and everything after it in the message.
+pub fn block_quote(text: impl Display) -> String {
+    format!(">>> {}", text)
+}
+
+/// A bulleted list, one item per line.
+pub fn bulleted_list<T: Display>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .map(|item| format!("- {}", item))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A numbered list starting at 1, one item per line.
+pub fn numbered_list<T: Display>(items: impl IntoIterator<Item = T>) -> String {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| format!("{}. {}", index + 1, item))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A link showing `text` instead of the url. Works in embeds, and in message content sent by webhooks.
+///
+/// # Example
+/// ```
+/// use diswh_esp::markdown;
+///
+/// assert_eq!(
+///     markdown::masked_link("Dashboard", "https://grafana.example.com/d/(boiler)"),
+///     "[Dashboard](https://grafana.example.com/d/%28boiler%29)"
+/// );
+/// ```
+pub fn masked_link(text: impl Display, url: &str) -> String {
+    format!("[{}]({})", text, url.replace('(', "%28").replace(')', "%29"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_leaves_decimal_numbers_alone() {
+        assert_eq!(escape("72.5"), "72.5");
+        assert_eq!(escape("3.3 V\n0.5 A"), "3.3 V\n0.5 A");
+    }
+
+    #[test]
+    fn escape_breaks_numbered_lists() {
+        assert_eq!(escape("1. first\n  12. twelfth"), "1\\. first\n  12\\. twelfth");
+    }
+
+    #[test]
+    fn escape_breaks_line_start_formatting() {
+        assert_eq!(escape("# header\n- item\n+ item"), "\\# header\n\\- item\n\\+ item");
+        assert_eq!(escape("a - b"), "a - b");
+    }
+
+    #[test]
+    fn escape_code_block_breaks_fences() {
+        assert!(!escape_code_block("```rust").contains("```"));
+        assert_eq!(code_block("", "```"), "```\n`\u{200b}`\u{200b}`\n```");
+    }
+}
@@ -0,0 +1,52 @@
+use std::fmt;
+
+use super::Snowflake;
+
+/// A mention of a user, role or channel, formatted into discord markup with [Display](fmt::Display).
+///
+/// Mentions in message content only ping when the webhook is allowed to, but are always shown as names.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{Mention, Snowflake};
+///
+/// let content = format!("{} boiler over temperature", Mention::Role(Snowflake::new(123456789012345678)));
+/// assert_eq!(content, "<@&123456789012345678> boiler over temperature");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mention {
+    User(Snowflake),
+    Role(Snowflake),
+    Channel(Snowflake),
+    /// Everyone in the channel.
+    Everyone,
+    /// Everyone in the channel that is online.
+    Here,
+}
+
+impl fmt::Display for Mention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User(id) => write!(f, "<@{}>", id),
+            Self::Role(id) => write!(f, "<@&{}>", id),
+            Self::Channel(id) => write!(f, "<#{}>", id),
+            Self::Everyone => f.write_str("@everyone"),
+            Self::Here => f.write_str("@here"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_use_discord_markup() {
+        let id = Snowflake::new(123456789012345678);
+        assert_eq!(Mention::User(id).to_string(), "<@123456789012345678>");
+        assert_eq!(Mention::Role(id).to_string(), "<@&123456789012345678>");
+        assert_eq!(Mention::Channel(id).to_string(), "<#123456789012345678>");
+        assert_eq!(Mention::Everyone.to_string(), "@everyone");
+        assert_eq!(Mention::Here.to_string(), "@here");
+    }
+}
@@ -1,43 +1,131 @@
-use std::collections::VecDeque;
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
 
-use serde::Serialize;
-use serde_json::{json, Value};
+use super::{
+    attachment::{self, Attachment},
+    component::{self, Component},
+    edit::EditMessagePacket,
+    embed::Embed,
+    flags::MessageFlags,
+    poll::Poll,
+};
 
-use super::embed::Embed;
+/// The most characters discord accepts in a message's content.
+pub(crate) const CONTENT_LIMIT: usize = 2000;
 
 /// A message packet contains all the data required by discord to send a message. Empty strings will be ignored however.
 /// 
 /// When sending it to discord, at __least__ 1 embed or `content` must contain data.
-#[derive(Clone, Serialize)]
+///
+/// Packets can be deserialized as well, which allows loading message templates from JSON or storing them for later.
+/// Missing fields fall back to their defaults and unknown fields are ignored.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{EmbedBuilder, MessageBuilder, MessagePacket};
+///
+/// let packet = MessageBuilder::new("Hello, world!", false)
+///     .with_username("Sensor 1")
+///     .add_embed(EmbedBuilder::new().with_title("Temperature").build())
+///     .build();
+///
+/// let json = serde_json::to_string(&packet).unwrap();
+/// let parsed: MessagePacket = serde_json::from_str(&json).unwrap();
+/// assert_eq!(packet, parsed);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MessagePacket {
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub content: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub username: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub avatar_url: String,
     pub tts: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub embeds: Vec<Embed>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Component>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll: Option<Poll>,
+    /// Files uploaded with the message, only their metadata is part of the JSON and they are never deserialized.
+    #[serde(
+        rename = "attachments",
+        serialize_with = "attachment::serialize_metadata",
+        skip_serializing_if = "Vec::is_empty",
+        skip_deserializing
+    )]
+    pub files: Vec<Attachment>,
+    /// See [super::MessageFlags].
+    #[serde(skip_serializing_if = "is_zero")]
+    pub flags: u64,
 }
 
 impl MessagePacket {
-    pub(crate) fn serialize_packet(&self) -> String {
-        let mut j = json!({});
-        if !self.content.is_empty() {
-            j["content"] = json!(self.content);
+    /// Checks the poll and components against discord's limits, and that Components V2 messages have no content or embeds.
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if let Some(poll) = &self.poll {
+            poll.validate()?;
         }
-        if !self.username.is_empty() {
-            j["username"] = json!(self.username);
-        }
-        if !self.avatar_url.is_empty() {
-            j["avatar_url"] = json!(self.avatar_url);
-        }
-        j["tts"] = json!(self.tts);
-        if !self.embeds.is_empty() {
-            let mut embeds_json = VecDeque::new();
-            for embed in &self.embeds {
-                let e: Value = serde_json::to_value(embed).unwrap();
-                embeds_json.push_back(e);
-            }
-            j["embeds"] = json!(embeds_json);
+        validate(&self.content, &self.embeds, &self.components, self.flags)
+    }
+}
+
+pub(crate) fn is_zero(flags: &u64) -> bool {
+    *flags == 0
+}
+
+pub(crate) fn validate(content: &str, embeds: &[Embed], components: &[Component], flags: u64) -> anyhow::Result<()> {
+    if flags & MessageFlags::IS_COMPONENTS_V2 != 0 && (!content.is_empty() || !embeds.is_empty()) {
+        bail!("messages with the IS_COMPONENTS_V2 flag can not have content or embeds");
+    }
+    component::validate(components, flags)
+}
+
+impl From<EditMessagePacket> for MessagePacket {
+    fn from(packet: EditMessagePacket) -> Self {
+        Self {
+            content: packet.content,
+            embeds: packet.embeds,
+            components: packet.components.unwrap_or_default(),
+            flags: packet.flags,
+            ..Default::default()
         }
-        j.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmbedBuilder;
+
+    #[test]
+    fn round_trips_through_json() {
+        let packet = MessagePacket {
+            content: "Hello".into(),
+            username: "Sensor 1".into(),
+            embeds: vec![EmbedBuilder::new().with_title("Temperature").add_field("Now", "21 °C", true).build()],
+            flags: MessageFlags::SUPPRESS_NOTIFICATIONS,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&packet).unwrap();
+        assert_eq!(serde_json::from_str::<MessagePacket>(&json).unwrap(), packet);
+    }
+
+    #[test]
+    fn ignores_unknown_and_missing_fields() {
+        let packet: MessagePacket =
+            serde_json::from_str(r#"{"content": "Hi", "embeds": [{"type": "rich", "title": "T"}], "nonce": "1"}"#).unwrap();
+        assert_eq!(packet.content, "Hi");
+        assert_eq!(packet.embeds[0].title.as_deref(), Some("T"));
+        assert!(!packet.tts);
+        assert_eq!(packet.flags, 0);
+    }
+
+    #[test]
+    fn skips_empty_fields() {
+        let json = serde_json::to_value(MessagePacket::default()).unwrap();
+        assert_eq!(json, serde_json::json!({"tts": false}));
     }
 }
@@ -1,4 +1,11 @@
-use super::{embed::Embed, message::MessagePacket};
+use super::{
+    attachment::Attachment,
+    component::{ActionRow, Component},
+    flags::MessageFlags,
+    embed::Embed,
+    message::MessagePacket,
+    poll::Poll,
+};
 
 #[derive(Clone)]
 pub struct MessageBuilder {
@@ -21,6 +28,10 @@ impl MessageBuilder {
                 avatar_url: "".into(),
                 tts,
                 embeds: Vec::new(),
+                components: Vec::new(),
+                poll: None,
+                files: Vec::new(),
+                flags: 0,
             },
         }
     }
@@ -43,6 +54,23 @@ impl MessageBuilder {
         self
     }
 
+    /// Sets message flags, such as [super::MessageFlags::SUPPRESS_NOTIFICATIONS] to send the message silently.
+    ///
+    /// The flags are added to any flags already set.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use diswh_esp::{MessageBuilder, MessageFlags};
+    ///
+    /// let message = MessageBuilder::new("Nightly report", false)
+    ///     .with_flags(MessageFlags::SUPPRESS_NOTIFICATIONS)
+    ///     .build();
+    /// ```
+    pub fn with_flags(mut self, flags: u64) -> Self {
+        self.message.flags |= flags;
+        self
+    }
+
     /// Adds an embed to the message packet.
     /// 
     /// # Note
@@ -54,6 +82,60 @@ impl MessageBuilder {
         self
     }
 
+    /// Adds a row of buttons or a select menu to the message.
+    ///
+    /// Webhooks not owned by an application can only send [super::Button::link] buttons.
+    ///
+    /// # Panics
+    /// Will panic if the message already holds 5 action rows.
+    pub fn add_action_row(mut self, row: ActionRow) -> Self {
+        assert!(self.message.components.len() < 5, "a message holds at most 5 action rows");
+        self.message.components.push(Component::ActionRow(row));
+        self
+    }
+
+    /// Adds a component to the message, such as a [super::Container] built with [super::ContainerBuilder].
+    ///
+    /// This marks the message with [super::MessageFlags::IS_COMPONENTS_V2], so it can no longer have content or
+    /// embeds. Up to 40 components can be used, counting nested ones.
+    pub fn add_component(mut self, component: impl Into<Component>) -> Self {
+        self.message.flags |= MessageFlags::IS_COMPONENTS_V2;
+        self.message.components.push(component.into());
+        self
+    }
+
+    /// Attaches a poll to the message, use the provided [super::PollBuilder] to construct it.
+    ///
+    /// Polls can not be edited, and the votes can be read later with [super::WebhookBuilder::get_message].
+    pub fn with_poll(mut self, poll: Poll) -> Self {
+        self.message.poll = Some(poll);
+        self
+    }
+
+    /// Uploads a file with the message, shown by embeds and components through [Attachment::url].
+    ///
+    /// Messages with files are sent as `multipart/form-data`, and a message holds at most 10 of them.
+    ///
+    /// # Panics
+    /// Will panic if the message already holds 10 files.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use diswh_esp::{Attachment, EmbedBuilder, MessageBuilder};
+    ///
+    /// # let png = Vec::new();
+    /// let chart = Attachment::new("chart.png", "image/png", png);
+    /// let message = MessageBuilder::new("", false)
+    ///     .add_embed(EmbedBuilder::new().with_image(chart.url()).build())
+    ///     .add_file(chart)
+    ///     .build();
+    /// ```
+    pub fn add_file(mut self, file: Attachment) -> Self {
+        assert!(self.message.files.len() < 10, "a message holds at most 10 files");
+        self.message.files.push(file);
+        self
+    }
+
     /// Decomposes the Message builder into its base packet.
     /// 
     /// # Warning
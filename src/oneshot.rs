@@ -0,0 +1,181 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+/// A single use channel that can be awaited from any executor or waited on from a plain thread.
+///
+/// Resolves to `None` when the sender is dropped without sending, for example because the sending thread panicked.
+pub(crate) fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            value: None,
+            closed: false,
+            waker: None,
+        }),
+        ready: Condvar::new(),
+    });
+    (
+        Sender {
+            shared: shared.clone(),
+        },
+        Receiver { shared },
+    )
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    ready: Condvar,
+}
+
+struct State<T> {
+    value: Option<T>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+pub(crate) struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    pub(crate) fn send(self, value: T) {
+        self.shared.state.lock().unwrap().value = Some(value);
+        // Dropping self closes the channel and wakes the receiver.
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        let waker = {
+            let mut state = self.shared.state.lock().unwrap();
+            state.closed = true;
+            state.waker.take()
+        };
+        self.shared.ready.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+pub(crate) struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    /// Blocks the current thread until the value arrives.
+    pub(crate) fn wait(self) -> Option<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        while !state.closed {
+            state = self.shared.ready.wait(state).unwrap();
+        }
+        state.value.take()
+    }
+
+    /// Returns true once the value has arrived or the sender is gone.
+    pub(crate) fn is_ready(&self) -> bool {
+        self.shared.state.lock().unwrap().closed
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Option<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+        if state.closed {
+            return Poll::Ready(state.value.take());
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Runs a future to completion on the current thread, parking it while the future is pending.
+#[cfg(test)]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    use std::task::Wake;
+
+    struct Unpark(std::thread::Thread);
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = std::pin::pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        std::thread::park();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        task::Wake,
+        thread,
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn poll<T>(receiver: &mut Receiver<T>, waker: &Arc<CountingWaker>) -> Poll<Option<T>> {
+        let waker = Waker::from(waker.clone());
+        Pin::new(receiver).poll(&mut Context::from_waker(&waker))
+    }
+
+    #[test]
+    fn sending_wakes_the_pending_receiver() {
+        let (sender, mut receiver) = channel();
+        let waker = Arc::new(CountingWaker::default());
+        assert!(poll(&mut receiver, &waker).is_pending());
+        assert_eq!(waker.0.load(Ordering::SeqCst), 0);
+
+        thread::spawn(move || sender.send(7)).join().unwrap();
+        assert_eq!(waker.0.load(Ordering::SeqCst), 1);
+        assert!(receiver.is_ready());
+        assert_eq!(poll(&mut receiver, &waker), Poll::Ready(Some(7)));
+    }
+
+    #[test]
+    fn dropping_the_sender_resolves_to_none() {
+        let (sender, mut receiver) = channel::<u8>();
+        let waker = Arc::new(CountingWaker::default());
+        assert!(poll(&mut receiver, &waker).is_pending());
+        drop(sender);
+        assert_eq!(waker.0.load(Ordering::SeqCst), 1);
+        assert_eq!(poll(&mut receiver, &waker), Poll::Ready(None));
+    }
+
+    #[test]
+    fn waiting_and_awaiting_get_the_value_from_another_thread() {
+        let (sender, receiver) = channel();
+        let handle = thread::spawn(move || receiver.wait());
+        sender.send("waited");
+        assert_eq!(handle.join().unwrap(), Some("waited"));
+
+        let (sender, receiver) = channel();
+        let handle = thread::spawn(move || block_on(receiver));
+        thread::sleep(std::time::Duration::from_millis(10));
+        sender.send("awaited");
+        assert_eq!(handle.join().unwrap(), Some("awaited"));
+    }
+}
@@ -0,0 +1,321 @@
+//! A small PNG encoder for palette images, enough for the flat colored charts of [super::Chart].
+//!
+//! Pixels are compressed with fixed Huffman codes, repeating runs along a row and rows that repeat the one above,
+//! which shrinks the large single colored areas of a chart without any dependencies.
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// The longest match deflate can express.
+const MAX_MATCH: usize = 258;
+/// The shortest match worth encoding.
+const MIN_MATCH: usize = 3;
+
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// Encodes an 8 bit palette image, `pixels` holding one palette index per pixel, row by row.
+///
+/// # Panics
+/// Will panic if `pixels` does not match the size of the image, the palette holds more than 256 colors, or a row is
+/// too long to be matched against the row above.
+pub(crate) fn encode(width: u32, height: u32, palette: &[[u8; 3]], pixels: &[u8]) -> Vec<u8> {
+    assert_eq!(pixels.len(), width as usize * height as usize, "pixels do not match the image size");
+    assert!(!palette.is_empty() && palette.len() <= 256, "a palette holds 1 to 256 colors");
+    let stride = width as usize + 1;
+    assert!(stride <= 32768, "rows are at most 32767 pixels wide");
+
+    // Every row starts with its filter type, 0 leaves the row as is.
+    let mut scanlines = Vec::with_capacity(stride * height as usize);
+    for row in pixels.chunks(width as usize) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // Bit depth 8, color type 3 (palette), default compression and filtering, no interlacing.
+    header.extend_from_slice(&[8, 3, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"PLTE", &palette.concat());
+    write_chunk(&mut png, b"IDAT", &zlib(&scanlines, stride));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps a single deflate block in a zlib stream.
+fn zlib(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    // The final block, compressed with fixed Huffman codes.
+    bits.write(1, 1);
+    bits.write(1, 2);
+
+    let mut position = 0;
+    while position < data.len() {
+        let (length, distance) = [1, stride]
+            .into_iter()
+            .filter(|distance| *distance <= position)
+            .map(|distance| (match_length(data, position, distance), distance))
+            .max_by_key(|(length, _)| *length)
+            .unwrap_or((0, 0));
+        if length >= MIN_MATCH {
+            bits.write_length(length);
+            bits.write_distance(distance);
+            position += length;
+        } else {
+            bits.write_symbol(data[position] as u16);
+            position += 1;
+        }
+    }
+    bits.write_symbol(256);
+
+    let mut stream = vec![0x78, 0x01];
+    stream.extend(bits.finish());
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// How many bytes from `position` on repeat the bytes `distance` before them.
+fn match_length(data: &[u8], position: usize, distance: usize) -> usize {
+    data[position..]
+        .iter()
+        .take(MAX_MATCH)
+        .zip(&data[position - distance..])
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Writes deflate's bit stream, least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.buffer |= value << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Huffman codes are stored most significant bit first.
+    fn write_code(&mut self, code: u32, length: u32) {
+        self.write(code.reverse_bits() >> (32 - length), length);
+    }
+
+    /// Writes a literal byte, the end of the block or a length code with the fixed Huffman codes.
+    fn write_symbol(&mut self, symbol: u16) {
+        let symbol = symbol as u32;
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xc0 + symbol - 280, 8),
+        }
+    }
+
+    fn write_length(&mut self, length: usize) {
+        let code = LENGTH_BASES.iter().rposition(|base| *base as usize <= length).unwrap_or(0);
+        self.write_symbol(257 + code as u16);
+        self.write((length - LENGTH_BASES[code] as usize) as u32, LENGTH_EXTRA_BITS[code] as u32);
+    }
+
+    fn write_distance(&mut self, distance: usize) {
+        let code = DISTANCE_BASES.iter().rposition(|base| *base as usize <= distance).unwrap_or(0);
+        self.write_code(code as u32, 5);
+        self.write((distance - DISTANCE_BASES[code] as usize) as u32, DISTANCE_EXTRA_BITS[code] as u32);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads deflate's bit stream, the counterpart of [BitWriter].
+    struct BitReader<'a> {
+        bytes: &'a [u8],
+        position: usize,
+    }
+
+    impl BitReader<'_> {
+        fn bit(&mut self) -> u32 {
+            let bit = (self.bytes[self.position / 8] >> (self.position % 8)) & 1;
+            self.position += 1;
+            bit as u32
+        }
+
+        fn bits(&mut self, count: u32) -> u32 {
+            (0..count).fold(0, |value, i| value | self.bit() << i)
+        }
+
+        /// Reads a fixed Huffman literal or length symbol, its code stored most significant bit first.
+        fn symbol(&mut self) -> u16 {
+            let mut code = 0;
+            for length in 1..=9 {
+                code = code << 1 | self.bit();
+                match (length, code) {
+                    (7, 0..=0x17) => return 256 + code as u16,
+                    (8, 0x30..=0xbf) => return code as u16 - 0x30,
+                    (8, 0xc0..=0xc7) => return 280 + code as u16 - 0xc0,
+                    (9, 0x190..=0x1ff) => return 144 + code as u16 - 0x190,
+                    _ => {}
+                }
+            }
+            panic!("invalid fixed Huffman code");
+        }
+    }
+
+    /// Inflates a zlib stream made of fixed Huffman blocks, checking its header and checksum.
+    fn inflate(stream: &[u8]) -> Vec<u8> {
+        assert_eq!((stream[0] as u16 * 256 + stream[1] as u16) % 31, 0, "invalid zlib header");
+        let (deflate, checksum) = stream[2..].split_at(stream.len() - 6);
+        let mut bits = BitReader { bytes: deflate, position: 0 };
+        let mut data: Vec<u8> = Vec::new();
+        loop {
+            let last = bits.bit();
+            assert_eq!(bits.bits(2), 1, "only fixed Huffman blocks are expected");
+            loop {
+                match bits.symbol() {
+                    literal @ 0..=255 => data.push(literal as u8),
+                    256 => break,
+                    symbol => {
+                        let code = (symbol - 257) as usize;
+                        let length = LENGTH_BASES[code] as usize + bits.bits(LENGTH_EXTRA_BITS[code] as u32) as usize;
+                        let code = (0..5).fold(0, |code, _| code << 1 | bits.bit()) as usize;
+                        let distance =
+                            DISTANCE_BASES[code] as usize + bits.bits(DISTANCE_EXTRA_BITS[code] as u32) as usize;
+                        for _ in 0..length {
+                            data.push(data[data.len() - distance]);
+                        }
+                    }
+                }
+            }
+            if last == 1 {
+                break;
+            }
+        }
+        assert_eq!(checksum, adler32(&data).to_be_bytes(), "adler32 mismatch");
+        data
+    }
+
+    /// Splits a PNG into its chunks, checking the signature and every chunk's CRC.
+    fn chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        assert_eq!(png[..8], SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+            let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+            assert_eq!(crc, crc32(&rest[4..8 + length]), "CRC mismatch in {:?}", kind);
+            chunks.push((kind.try_into().unwrap(), data.to_vec()));
+            rest = &rest[12 + length..];
+        }
+        chunks
+    }
+
+    fn round_trip(width: u32, height: u32, pixels: &[u8]) {
+        let palette = [[0, 0, 0], [255, 255, 255], [255, 0, 0]];
+        let png = encode(width, height, &palette, pixels);
+        let chunks = chunks(&png);
+        let kinds: Vec<&[u8; 4]> = chunks.iter().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"PLTE", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1[..8], [width.to_be_bytes(), height.to_be_bytes()].concat());
+        assert_eq!(chunks[1].1, palette.concat());
+
+        let scanlines = inflate(&chunks[2].1);
+        let expected: Vec<u8> = pixels.chunks(width as usize).flat_map(|row| [&[0][..], row].concat()).collect();
+        assert_eq!(scanlines, expected);
+    }
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn round_trips_flat_images() {
+        // Long runs and repeated rows, like the background of a chart.
+        let (width, height) = (600, 40);
+        let mut pixels = vec![0u8; width * height];
+        for (i, pixel) in pixels.iter_mut().enumerate() {
+            if i % width == 17 || i / width == 20 {
+                *pixel = 2;
+            }
+        }
+        round_trip(width as u32, height as u32, &pixels);
+    }
+
+    #[test]
+    fn round_trips_noisy_images() {
+        let mut seed = 1u32;
+        let pixels: Vec<u8> = (0..37 * 23)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8 % 3
+            })
+            .collect();
+        round_trip(37, 23, &pixels);
+    }
+
+    #[test]
+    fn round_trips_tiny_images() {
+        round_trip(1, 1, &[1]);
+        round_trip(1, 5, &[0, 1, 2, 1, 0]);
+    }
+}
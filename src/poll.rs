@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use super::PartialEmoji;
+
+/// The most answers discord accepts on a poll.
+const ANSWER_LIMIT: usize = 10;
+/// The longest a poll can run, 32 days.
+const MAX_DURATION_HOURS: u32 = 768;
+
+/// The text and emoji of a poll's question or answer.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollMedia {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<PartialEmoji>,
+}
+
+/// One answer of a [Poll].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollAnswer {
+    /// Assigned by discord, only set on polls returned by discord.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_id: Option<u32>,
+    pub poll_media: PollMedia,
+}
+
+/// How many votes one answer received.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollAnswerCount {
+    /// The [PollAnswer::answer_id] the votes are for.
+    pub id: u32,
+    pub count: u32,
+    pub me_voted: bool,
+}
+
+/// The votes of a poll, as returned by discord.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollResults {
+    /// Whether the poll has ended and the counts are final.
+    pub is_finalized: bool,
+    /// Answers nobody voted for are left out.
+    pub answer_counts: Vec<PollAnswerCount>,
+}
+
+/// A poll attached to a message.
+///
+/// Use the provided [PollBuilder] to aid you in constructing the object. Polls returned by discord, from
+/// [super::WebhookBuilder::get_message], carry the expiry and the results as well.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Poll {
+    pub question: PollMedia,
+    pub answers: Vec<PollAnswer>,
+    /// How many hours the poll runs for, only used when creating it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    /// When the poll ends, as an ISO8601 timestamp. Only set on polls returned by discord.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<String>,
+    pub allow_multiselect: bool,
+    /// Only set on polls returned by discord, and may be missing while the votes are counted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<PollResults>,
+}
+
+impl Poll {
+    /// Checks the poll against discord's limits before it is sent.
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if self.answers.is_empty() {
+            bail!("a poll needs at least one answer");
+        }
+        if self.answers.len() > ANSWER_LIMIT {
+            bail!("a poll has at most {} answers", ANSWER_LIMIT);
+        }
+        if self.duration.is_some_and(|hours| hours > MAX_DURATION_HOURS) {
+            bail!("a poll runs for at most {} hours", MAX_DURATION_HOURS);
+        }
+        Ok(())
+    }
+
+    /// Returns how many votes the answer with the text `answer` received.
+    ///
+    /// Returns 0 when there is no such answer, or discord did not include results.
+    pub fn votes(&self, answer: &str) -> u32 {
+        let Some(id) = self
+            .answers
+            .iter()
+            .find(|candidate| candidate.poll_media.text.as_deref() == Some(answer))
+            .and_then(|candidate| candidate.answer_id)
+        else {
+            return 0;
+        };
+        self.results
+            .iter()
+            .flat_map(|results| &results.answer_counts)
+            .find(|count| count.id == id)
+            .map_or(0, |count| count.count)
+    }
+
+    /// Returns whether the poll has ended and its results are final.
+    pub fn is_finalized(&self) -> bool {
+        self.results.as_ref().is_some_and(|results| results.is_finalized)
+    }
+}
+
+/// A helper struct to make it easy to construct a [Poll] using a chain style.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+///
+/// use diswh_esp::{MessageBuilder, PartialEmoji, PollBuilder};
+///
+/// let poll = PollBuilder::new("Acknowledge alarm?")
+///     .add_answer_with_emoji("Yes", PartialEmoji::unicode("✅"))
+///     .add_answer("No")
+///     .with_duration(Duration::from_secs(3600))
+///     .build();
+/// let packet = MessageBuilder::new("", false).with_poll(poll).build();
+///
+/// assert_eq!(
+///     serde_json::to_value(&packet).unwrap(),
+///     serde_json::json!({
+///         "tts": false,
+///         "poll": {
+///             "question": { "text": "Acknowledge alarm?" },
+///             "answers": [
+///                 { "poll_media": { "text": "Yes", "emoji": { "name": "✅" } } },
+///                 { "poll_media": { "text": "No" } }
+///             ],
+///             "duration": 1,
+///             "allow_multiselect": false
+///         }
+///     })
+/// );
+/// ```
+#[derive(Clone)]
+pub struct PollBuilder {
+    poll: Poll,
+}
+
+impl PollBuilder {
+    /// Initializes a poll asking `question`, running for discord's default of 24 hours.
+    ///
+    /// # Panics
+    /// Will panic when the provided `question` is not able to be converted into a [String]
+    pub fn new(question: impl Into<String>) -> Self {
+        Self {
+            poll: Poll {
+                question: PollMedia {
+                    text: Some(question.into()),
+                    emoji: None,
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Adds an answer.
+    ///
+    /// # Panics
+    /// Will panic when the poll already has 10 answers.
+    pub fn add_answer(self, text: impl Into<String>) -> Self {
+        self.push_answer(text.into(), None)
+    }
+
+    /// Adds an answer with an emoji in front of it.
+    ///
+    /// # Panics
+    /// Will panic when the poll already has 10 answers.
+    pub fn add_answer_with_emoji(self, text: impl Into<String>, emoji: PartialEmoji) -> Self {
+        self.push_answer(text.into(), Some(emoji))
+    }
+
+    /// Sets how long the poll runs, rounded up to whole hours.
+    ///
+    /// # Panics
+    /// Will panic when `duration` is longer than 32 days.
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        let hours = duration.as_secs().div_ceil(3600).max(1);
+        assert!(
+            hours <= MAX_DURATION_HOURS as u64,
+            "a poll runs for at most {} hours",
+            MAX_DURATION_HOURS
+        );
+        self.poll.duration = Some(hours as u32);
+        self
+    }
+
+    /// Lets voters pick more than one answer.
+    pub fn with_multiselect(mut self, allow_multiselect: bool) -> Self {
+        self.poll.allow_multiselect = allow_multiselect;
+        self
+    }
+
+    /// Decompose the inner poll into a owned object
+    ///
+    /// Sending a message with a poll that has no answers fails.
+    ///
+    /// # Warning
+    /// This action is destructive, the poll builder will no longer exist after performing this action.
+    pub fn build(self) -> Poll {
+        self.poll
+    }
+
+    fn push_answer(mut self, text: String, emoji: Option<PartialEmoji>) -> Self {
+        assert!(
+            self.poll.answers.len() < ANSWER_LIMIT,
+            "a poll has at most {} answers",
+            ANSWER_LIMIT
+        );
+        self.poll.answers.push(PollAnswer {
+            answer_id: None,
+            poll_media: PollMedia { text: Some(text), emoji },
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_polls_without_answers() {
+        let error = PollBuilder::new("Anyone?").build().validate().unwrap_err();
+        assert_eq!(error.to_string(), "a poll needs at least one answer");
+        assert!(PollBuilder::new("Anyone?").add_answer("Me").build().validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_polls_past_the_limits() {
+        let mut poll = PollBuilder::new("Pick one").add_answer("A").build();
+        poll.answers = vec![poll.answers[0].clone(); ANSWER_LIMIT + 1];
+        assert!(poll.validate().is_err());
+        poll.answers.truncate(1);
+        poll.duration = Some(MAX_DURATION_HOURS + 1);
+        assert!(poll.validate().is_err());
+    }
+}
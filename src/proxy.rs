@@ -0,0 +1,241 @@
+use std::{
+    fmt,
+    io::{self, BufReader, Read, Write},
+    net::TcpStream,
+};
+
+use anyhow::bail;
+use esp_idf_svc::{
+    http::Method,
+    sys,
+    tls::{self, EspTls},
+};
+
+use super::{
+    config::WebhookConfig,
+    raw_http::{self, Target},
+    sha256::sha256,
+    stream::EmbeddedIo,
+};
+
+/// How requests are passed through a [ProxyConfig].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProxyMode {
+    /// Open a tunnel with `CONNECT` and speak TLS to discord through it, the proxy never sees the request.
+    Connect,
+    /// Send the request in plain http with the full url as target, the proxy or relay makes the request to discord.
+    ///
+    /// Only use this with a relay you trust on a network you trust, the webhook token travels unencrypted to it.
+    Forward,
+}
+
+/// An http proxy requests are routed through.
+///
+/// The password is redacted from the [fmt::Debug] output.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{ProxyConfig, WebhookBuilder, WebhookConfig};
+///
+/// let webhook = WebhookBuilder::new("https://discord.com/api/webhooks/1234/token")
+///     .with_config(
+///         WebhookConfig::new()
+///             .with_proxy(ProxyConfig::connect("proxy.example.com", 3128).with_basic_auth("user", "password"))
+///     );
+/// ```
+#[derive(Clone)]
+pub struct ProxyConfig {
+    pub host: String,
+    pub port: u16,
+    pub mode: ProxyMode,
+    pub credentials: Option<(String, String)>,
+}
+
+impl ProxyConfig {
+    /// A proxy that tunnels requests with `CONNECT`.
+    ///
+    /// # Panics
+    /// Will panic if the provided `host` can not be converted into a [String]
+    pub fn connect(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            mode: ProxyMode::Connect,
+            credentials: None,
+        }
+    }
+
+    /// A plain http proxy or relay that is sent the full url of each request.
+    ///
+    /// # Panics
+    /// Will panic if the provided `host` can not be converted into a [String]
+    pub fn forward(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            mode: ProxyMode::Forward,
+            credentials: None,
+        }
+    }
+
+    /// Authenticates to the proxy with http basic auth.
+    ///
+    /// # Panics
+    /// Will panic if either `username` or `password` are unable to be converted into a [String]
+    pub fn with_basic_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    fn authorization(&self) -> Option<String> {
+        self.credentials
+            .as_ref()
+            .map(|(user, password)| format!("Basic {}", raw_http::base64(format!("{}:{}", user, password).as_bytes())))
+    }
+}
+
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("mode", &self.mode)
+            .field("username", &self.credentials.as_ref().map(|(user, _)| user))
+            .finish()
+    }
+}
+
+/// A connection opened by hand, to a proxy or straight to the server, after any tunnel has been set up.
+enum RawStream {
+    Plain(TcpStream),
+    Tls(EmbeddedIo<EspTls<TcpStream>>),
+}
+
+impl Read for RawStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            RawStream::Plain(stream) => stream.read(buf),
+            RawStream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for RawStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            RawStream::Plain(stream) => stream.write(buf),
+            RawStream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            RawStream::Plain(stream) => stream.flush(),
+            RawStream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// An open request over a connection opened by hand, the body is written into it before reading the response.
+///
+/// Used instead of the ESP http client for proxies, pinned certificates and separate connect and read timeouts.
+pub(crate) struct RawRequest {
+    stream: RawStream,
+}
+
+impl RawRequest {
+    /// Connects, through `proxy` if there is one, and sends the request line and headers of a request to `url`.
+    pub(crate) fn open(
+        proxy: Option<&ProxyConfig>,
+        config: &WebhookConfig,
+        method: Method,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> anyhow::Result<Self> {
+        let target = Target::parse(url)?;
+        let method = format!("{:?}", method).to_uppercase();
+        let forward = proxy.is_some_and(|proxy| proxy.mode == ProxyMode::Forward);
+        if config.pinned_certificate.is_some() && (forward || !target.https) {
+            bail!("a pinned certificate needs an https url that is not sent through a forward proxy");
+        }
+        let socket = match proxy {
+            Some(proxy) => raw_http::connect(&proxy.host, proxy.port, config.connect_timeout)?,
+            None => raw_http::connect(target.host, target.port, config.connect_timeout)?,
+        };
+        socket.set_read_timeout(config.read_timeout)?;
+        socket.set_write_timeout(config.read_timeout)?;
+
+        let authorization = proxy.and_then(ProxyConfig::authorization);
+        let host = target.host_header();
+        let (mut stream, head) = if forward {
+            let head = raw_http::request_head(&method, url, &host, authorization.as_deref(), headers);
+            (RawStream::Plain(socket), head)
+        } else {
+            let mut socket = socket;
+            if proxy.is_some() {
+                raw_http::open_tunnel(&mut socket, &target, authorization.as_deref())?;
+            }
+            let head = raw_http::request_head(&method, target.path, &host, None, headers);
+            if target.https {
+                (RawStream::Tls(EmbeddedIo(negotiate_tls(socket, target.host, config)?)), head)
+            } else {
+                (RawStream::Plain(socket), head)
+            }
+        };
+        stream.write_all(head.as_bytes())?;
+        Ok(Self { stream })
+    }
+
+    /// Finishes the request and returns the status along with a reader over the response body.
+    pub(crate) fn submit(mut self) -> anyhow::Result<(u16, Box<dyn Read>)> {
+        self.stream.flush()?;
+        raw_http::read_response(BufReader::new(self.stream))
+    }
+}
+
+impl Write for RawRequest {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+fn negotiate_tls(socket: TcpStream, host: &str, config: &WebhookConfig) -> anyhow::Result<EspTls<TcpStream>> {
+    let ca_certificate = config.ca_certificate()?;
+    let mut tls = EspTls::adopt(socket)?;
+    let mut tls_config = tls::Config::new();
+    tls_config.common_name = Some(host);
+    tls_config.use_global_ca_store = config.use_global_ca_store && ca_certificate.is_none();
+    tls_config.use_crt_bundle_attach = config.use_crt_bundle && ca_certificate.is_none();
+    tls_config.ca_cert = ca_certificate;
+    if let Some(timeout) = config.connect_timeout {
+        tls_config.timeout_ms = timeout.as_millis() as u32;
+    }
+    tls.negotiate(host, &tls_config)?;
+    if let Some(pinned) = config.pinned_certificate {
+        if peer_certificate_sha256(&tls)? != pinned {
+            bail!("server certificate does not match the pinned fingerprint");
+        }
+    }
+    Ok(tls)
+}
+
+/// Returns the SHA-256 fingerprint of the certificate the server presented during the handshake.
+fn peer_certificate_sha256(tls: &EspTls<TcpStream>) -> anyhow::Result<[u8; 32]> {
+    // SAFETY: the ssl context and the certificate it keeps belong to `tls`, which outlives this borrow of them.
+    unsafe {
+        let ssl = sys::esp_tls_get_ssl_context(tls.context_handle()) as *const sys::mbedtls_ssl_context;
+        if ssl.is_null() {
+            bail!("TLS connection has no ssl context");
+        }
+        let certificate = sys::mbedtls_ssl_get_peer_cert(ssl);
+        if certificate.is_null() {
+            bail!("server certificate was not kept, enable CONFIG_MBEDTLS_SSL_KEEP_PEER_CERTIFICATE");
+        }
+        let der = std::slice::from_raw_parts((*certificate).raw.p, (*certificate).raw.len);
+        Ok(sha256(der))
+    }
+}
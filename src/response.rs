@@ -0,0 +1,185 @@
+use std::{io::Read, time::Duration};
+
+use log::Level;
+
+/// How much of the response body sent back by discord should be read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResponseBody {
+    /// Do not read the body at all, only the status is kept.
+    Discard,
+    /// Read up to the given amount of bytes, anything past that is dropped.
+    Truncate(usize),
+    /// Read the whole body, however large it is.
+    ///
+    /// Be careful with this on small devices, discord error bodies can be several kilobytes.
+    Full,
+}
+
+/// Controls what happens with the response discord sends back after a request.
+///
+/// The default reads up to 1024 bytes of the body, logs it at [Level::Info] and redacts the webhook token from the logs.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{ResponseBody, ResponseConfig, WebhookBuilder};
+///
+/// let webhook = WebhookBuilder::new("https://discord.com/api/webhooks/1234/token")
+///     .with_response_config(
+///         ResponseConfig::new()
+///             .with_body(ResponseBody::Full)
+///             .with_log_level(Some(log::Level::Debug))
+///     );
+/// ```
+#[derive(Clone, Debug)]
+pub struct ResponseConfig {
+    pub body: ResponseBody,
+    pub log_level: Option<Level>,
+    pub redact: bool,
+}
+
+impl ResponseConfig {
+    /// Constructs the default response config.
+    pub fn new() -> Self {
+        Self {
+            body: ResponseBody::Truncate(1024),
+            log_level: Some(Level::Info),
+            redact: true,
+        }
+    }
+
+    /// Sets how much of the response body is read.
+    pub fn with_body(mut self, body: ResponseBody) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Sets the level requests and responses are logged at.
+    ///
+    /// `None` disables logging entirely.
+    pub fn with_log_level(mut self, level: Option<Level>) -> Self {
+        self.log_level = level;
+        self
+    }
+
+    /// Sets whether the webhook token is replaced with `[redacted]` in logged urls and bodies.
+    ///
+    /// # Warning
+    /// Turning this off will print your webhook secret to the logs.
+    pub fn with_redaction(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self
+    }
+}
+
+impl Default for ResponseConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The longest [WebhookResponse::retry_after] returns, so a bogus value can not block a sender for good.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(3600);
+
+/// The response discord sent back for a request.
+#[derive(Clone, Debug)]
+pub struct WebhookResponse {
+    /// The http status code.
+    pub status: u16,
+    /// The response body, as much of it as the [ResponseConfig] allowed to be read.
+    pub body: Vec<u8>,
+    /// Set when the body was cut short by [ResponseBody::Truncate].
+    pub truncated: bool,
+}
+
+impl WebhookResponse {
+    /// Returns true when the status code is in the 2xx range.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Returns the body as a string slice, if it is valid utf-8.
+    pub fn text(&self) -> Result<&str, std::str::Utf8Error> {
+        std::str::from_utf8(&self.body)
+    }
+
+    /// Returns how long discord asked to wait before retrying, for a 429 response with a `retry_after` in its body.
+    ///
+    /// Values that are negative or not a number are ignored, and waits are capped at [MAX_RETRY_AFTER].
+    pub fn retry_after(&self) -> Option<Duration> {
+        if self.status != 429 {
+            return None;
+        }
+        let seconds = serde_json::from_slice::<serde_json::Value>(&self.body)
+            .ok()?
+            .get("retry_after")?
+            .as_f64()?;
+        if seconds.is_nan() || seconds < 0.0 {
+            return None;
+        }
+        Some(Duration::try_from_secs_f64(seconds).map_or(MAX_RETRY_AFTER, |wait| wait.min(MAX_RETRY_AFTER)))
+    }
+}
+
+/// Reads the response body as instructed by `body`, returning the bytes read and whether it was truncated.
+pub(crate) fn read_body<R: Read>(mut reader: R, body: ResponseBody) -> anyhow::Result<(Vec<u8>, bool)> {
+    let mut buf = Vec::new();
+    match body {
+        ResponseBody::Discard => Ok((buf, false)),
+        ResponseBody::Truncate(limit) => {
+            (&mut reader).take(limit as u64).read_to_end(&mut buf)?;
+            let mut probe = [0u8; 1];
+            let truncated = buf.len() == limit && reader.read(&mut probe)? > 0;
+            Ok((buf, truncated))
+        }
+        ResponseBody::Full => {
+            reader.read_to_end(&mut buf)?;
+            Ok((buf, false))
+        }
+    }
+}
+
+/// Replaces every occurrence of `secret` in `text` with `[redacted]`.
+pub(crate) fn redact(text: &str, secret: &str) -> String {
+    if secret.is_empty() {
+        return text.to_string();
+    }
+    text.replace(secret, "[redacted]")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, body: &str) -> WebhookResponse {
+        WebhookResponse {
+            status,
+            body: body.as_bytes().to_vec(),
+            truncated: false,
+        }
+    }
+
+    #[test]
+    fn reads_retry_after() {
+        let wait = response(429, r#"{"message": "You are being rate limited.", "retry_after": 1.5, "global": false}"#);
+        assert_eq!(wait.retry_after(), Some(Duration::from_millis(1500)));
+        assert_eq!(response(200, r#"{"retry_after": 1.5}"#).retry_after(), None);
+        assert_eq!(response(429, "rate limited").retry_after(), None);
+    }
+
+    #[test]
+    fn rejects_bogus_retry_after() {
+        assert_eq!(response(429, r#"{"retry_after": -1}"#).retry_after(), None);
+        assert_eq!(response(429, r#"{"retry_after": "soon"}"#).retry_after(), None);
+        assert_eq!(response(429, r#"{"retry_after": 1e300}"#).retry_after(), Some(MAX_RETRY_AFTER));
+        assert_eq!(response(429, r#"{"retry_after": 86400}"#).retry_after(), Some(MAX_RETRY_AFTER));
+    }
+
+    #[test]
+    fn truncates_bodies() {
+        let (body, truncated) = read_body(&b"hello"[..], ResponseBody::Truncate(4)).unwrap();
+        assert_eq!((body.as_slice(), truncated), (&b"hell"[..], true));
+        let (body, truncated) = read_body(&b"hell"[..], ResponseBody::Truncate(4)).unwrap();
+        assert_eq!((body.as_slice(), truncated), (&b"hell"[..], false));
+        assert!(read_body(&b"hello"[..], ResponseBody::Discard).unwrap().0.is_empty());
+    }
+}
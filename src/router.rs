@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    message::CONTENT_LIMIT, Component, Mention, MessageFlags, MessagePacket, Snowflake, TargetResult, TextDisplay,
+    WebhookBuilder,
+};
+
+/// How urgent a routed message is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Debug,
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+/// A rule deciding which messages go to one webhook of a [Router].
+///
+/// A message matches when its severity is within the bounds and, if the rule lists topics, its topic is one of them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RouteRule {
+    /// The name of the webhook matching messages are sent to.
+    pub webhook: String,
+    #[serde(default = "RouteRule::lowest")]
+    pub min_severity: Severity,
+    #[serde(default = "RouteRule::highest")]
+    pub max_severity: Severity,
+    /// Topics this rule applies to, empty matches every topic.
+    #[serde(default)]
+    pub topics: Vec<String>,
+    /// Send without triggering notifications.
+    #[serde(default)]
+    pub silent: bool,
+    /// A role to mention at the start of the message, or in a text display ahead of the components of a Components V2
+    /// message.
+    #[serde(default)]
+    pub mention_role: Option<Snowflake>,
+}
+
+impl RouteRule {
+    /// Constructs a rule sending every message to the webhook named `webhook`.
+    ///
+    /// # Panics
+    /// Will panic if the provided `webhook` can not be converted into a [String]
+    pub fn new(webhook: impl Into<String>) -> Self {
+        Self {
+            webhook: webhook.into(),
+            min_severity: Self::lowest(),
+            max_severity: Self::highest(),
+            topics: Vec::new(),
+            silent: false,
+            mention_role: None,
+        }
+    }
+
+    /// Only match messages at or above `severity`.
+    pub fn with_min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+
+    /// Only match messages at or below `severity`.
+    pub fn with_max_severity(mut self, severity: Severity) -> Self {
+        self.max_severity = severity;
+        self
+    }
+
+    /// Only match messages with this topic, may be called multiple times to match several topics.
+    ///
+    /// # Panics
+    /// Will panic if the provided `topic` can not be converted into a [String]
+    pub fn add_topic(mut self, topic: impl Into<String>) -> Self {
+        self.topics.push(topic.into());
+        self
+    }
+
+    /// Send matching messages without triggering notifications.
+    pub fn with_silent(mut self, silent: bool) -> Self {
+        self.silent = silent;
+        self
+    }
+
+    /// Mention a role at the start of matching messages.
+    pub fn with_role_mention(mut self, role: impl Into<Snowflake>) -> Self {
+        self.mention_role = Some(role.into());
+        self
+    }
+
+    /// Returns true if a message with this severity and topic should be sent by this rule.
+    pub fn matches(&self, severity: Severity, topic: &str) -> bool {
+        (self.min_severity..=self.max_severity).contains(&severity)
+            && (self.topics.is_empty() || self.topics.iter().any(|t| t == topic))
+    }
+
+    /// Returns the message as this rule sends it, failing if the mention does not fit into its content.
+    fn apply(&self, packet: &MessagePacket) -> anyhow::Result<MessagePacket> {
+        let mut packet = packet.clone();
+        if self.silent {
+            packet.flags |= MessageFlags::SUPPRESS_NOTIFICATIONS;
+        }
+        let Some(role) = self.mention_role else {
+            return Ok(packet);
+        };
+        if packet.flags & MessageFlags::IS_COMPONENTS_V2 != 0 {
+            // Components V2 messages can not have content, the mention goes into a component of its own.
+            let mention = TextDisplay::new(Mention::Role(role).to_string());
+            packet.components.insert(0, Component::TextDisplay(mention));
+        } else {
+            packet.content = format!("{} {}", Mention::Role(role), packet.content);
+            if packet.content.chars().count() > CONTENT_LIMIT {
+                bail!("content is too long to mention role {}", role);
+            }
+        }
+        Ok(packet)
+    }
+
+    fn lowest() -> Severity {
+        Severity::Debug
+    }
+
+    fn highest() -> Severity {
+        Severity::Critical
+    }
+}
+
+/// The declarative form of a [Router], as loaded from JSON.
+///
+/// ```json
+/// {
+///     "webhooks": {
+///         "telemetry": "https://discord.com/api/webhooks/1234/token",
+///         "alerts": "https://discord.com/api/webhooks/5678/token"
+///     },
+///     "rules": [
+///         { "webhook": "telemetry", "max_severity": "info", "silent": true },
+///         { "webhook": "alerts", "min_severity": "critical", "mention_role": "112233" }
+///     ]
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RouterConfig {
+    /// Webhook urls by name.
+    pub webhooks: BTreeMap<String, String>,
+    pub rules: Vec<RouteRule>,
+}
+
+/// Dispatches messages to webhooks based on their severity and topic.
+///
+/// A message is sent to every webhook with a matching rule, messages matching no rule are not sent anywhere.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{MessageBuilder, RouteRule, Router, Severity, WebhookBuilder};
+///
+/// let router = Router::new()
+///     .add_webhook("telemetry", WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"))
+///     .add_webhook("alerts", WebhookBuilder::new("https://discord.com/api/webhooks/5678/token"))
+///     .add_rule(RouteRule::new("telemetry").with_max_severity(Severity::Info).with_silent(true))
+///     .add_rule(RouteRule::new("alerts").with_min_severity(Severity::Critical).with_role_mention(112233u64));
+///
+/// router.route(Severity::Critical, "boiler", &MessageBuilder::new("Boiler over temperature", false).build());
+/// ```
+#[derive(Clone, Default)]
+pub struct Router {
+    webhooks: BTreeMap<String, WebhookBuilder>,
+    rules: Vec<RouteRule>,
+}
+
+impl Router {
+    /// Constructs a router without webhooks or rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a router from its declarative config, failing if a url is invalid or a rule names an unknown webhook.
+    pub fn from_config(config: RouterConfig) -> anyhow::Result<Self> {
+        let mut router = Self::new();
+        for (name, url) in config.webhooks {
+            let webhook = WebhookBuilder::try_new(url).map_err(|e| anyhow!("webhook {}: {}", name, e))?;
+            router = router.add_webhook(name, webhook);
+        }
+        for rule in config.rules {
+            if !router.webhooks.contains_key(&rule.webhook) {
+                bail!("rule refers to unknown webhook {}", rule.webhook);
+            }
+            router = router.add_rule(rule);
+        }
+        Ok(router)
+    }
+
+    /// Builds a router from a JSON [RouterConfig].
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        Self::from_config(serde_json::from_str(json)?)
+    }
+
+    /// Adds a webhook that rules can refer to by `name`.
+    ///
+    /// # Panics
+    /// Will panic if the provided `name` can not be converted into a [String]
+    pub fn add_webhook(mut self, name: impl Into<String>, webhook: WebhookBuilder) -> Self {
+        self.webhooks.insert(name.into(), webhook);
+        self
+    }
+
+    /// Adds a routing rule.
+    pub fn add_rule(mut self, rule: RouteRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Sends `packet` to every webhook with a rule matching `severity` and `topic`.
+    ///
+    /// Returns one [TargetResult] per matching rule, named after the webhook it was sent to.
+    pub fn route(&self, severity: Severity, topic: &str, packet: &MessagePacket) -> Vec<TargetResult> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(severity, topic))
+            .map(|rule| TargetResult {
+                name: rule.webhook.clone(),
+                result: match self.webhooks.get(&rule.webhook) {
+                    Some(webhook) => rule
+                        .apply(packet)
+                        .and_then(|packet| webhook.send_message_with_response(&packet)),
+                    None => Err(anyhow!("rule refers to unknown webhook {}", rule.webhook)),
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBuilder;
+
+    #[test]
+    fn matches_severity_bounds_inclusively() {
+        let rule = RouteRule::new("alerts")
+            .with_min_severity(Severity::Info)
+            .with_max_severity(Severity::Error);
+        assert!(!rule.matches(Severity::Debug, "boiler"));
+        assert!(rule.matches(Severity::Info, "boiler"));
+        assert!(rule.matches(Severity::Warning, "boiler"));
+        assert!(rule.matches(Severity::Error, "boiler"));
+        assert!(!rule.matches(Severity::Critical, "boiler"));
+
+        let inverted = RouteRule::new("alerts")
+            .with_min_severity(Severity::Error)
+            .with_max_severity(Severity::Info);
+        assert!(!inverted.matches(Severity::Warning, "boiler"));
+    }
+
+    #[test]
+    fn matches_listed_topics_only() {
+        let any = RouteRule::new("alerts");
+        assert!(any.matches(Severity::Debug, "boiler"));
+        assert!(any.matches(Severity::Debug, ""));
+
+        let rule = RouteRule::new("alerts").add_topic("boiler").add_topic("pump");
+        assert!(rule.matches(Severity::Info, "boiler"));
+        assert!(rule.matches(Severity::Info, "pump"));
+        assert!(!rule.matches(Severity::Info, "Boiler"));
+        assert!(!rule.matches(Severity::Info, ""));
+    }
+
+    #[test]
+    fn loads_rules_with_defaults_from_json() {
+        let config: RouterConfig = serde_json::from_str(
+            r#"{
+                "webhooks": { "alerts": "https://discord.com/api/webhooks/1/t" },
+                "rules": [
+                    { "webhook": "alerts" },
+                    { "webhook": "alerts", "min_severity": "warning", "topics": ["boiler"], "silent": true,
+                      "mention_role": "112233" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let [all, boiler] = &config.rules[..] else {
+            panic!("expected two rules");
+        };
+        assert_eq!((all.min_severity, all.max_severity), (Severity::Debug, Severity::Critical));
+        assert!(all.topics.is_empty());
+        assert!(!all.silent);
+        assert_eq!(all.mention_role, None);
+        assert_eq!((boiler.min_severity, boiler.max_severity), (Severity::Warning, Severity::Critical));
+        assert_eq!(boiler.topics, ["boiler"]);
+        assert!(boiler.silent);
+        assert_eq!(boiler.mention_role, Some(Snowflake::from(112233u64)));
+
+        let router = Router::from_config(config).unwrap();
+        assert_eq!(router.webhooks.len(), 1);
+        assert_eq!(router.rules.len(), 2);
+        assert!(Router::from_json("{}").is_ok());
+        assert!(Router::from_json(r#"{ "rules": [{ "webhook": "alerts", "min_severity": "loud" }] }"#).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_webhooks_and_invalid_urls() {
+        let error = Router::from_json(r#"{ "rules": [{ "webhook": "alerts" }] }"#).err().unwrap();
+        assert_eq!(error.to_string(), "rule refers to unknown webhook alerts");
+
+        let error = Router::from_json(r#"{ "webhooks": { "alerts": "https://example.com/hook" } }"#).err().unwrap();
+        assert!(error.to_string().starts_with("webhook alerts: "), "{}", error);
+
+        // Rules added by hand are only checked when a message is routed.
+        let results = Router::new()
+            .add_rule(RouteRule::new("alerts"))
+            .route(Severity::Info, "boiler", &MessageBuilder::new("Boiler on", false).build());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].result.as_ref().unwrap_err().to_string(), "rule refers to unknown webhook alerts");
+    }
+
+    #[test]
+    fn routes_to_every_matching_rule() {
+        // Requests to hosts other than discord fail before touching the network, naming the host in the error.
+        let router = Router::new()
+            .add_webhook("telemetry", WebhookBuilder::new("https://telemetry.example/api/webhooks/1/t"))
+            .add_webhook("alerts", WebhookBuilder::new("https://alerts.example/api/webhooks/2/t"))
+            .add_rule(RouteRule::new("telemetry").with_max_severity(Severity::Info))
+            .add_rule(RouteRule::new("alerts").with_min_severity(Severity::Warning).add_topic("boiler"))
+            .add_rule(RouteRule::new("telemetry").with_min_severity(Severity::Critical));
+        let packet = MessageBuilder::new("Boiler over temperature", false).build();
+        let names = |severity, topic| {
+            let results = router.route(severity, topic, &packet);
+            for result in &results {
+                let error = result.result.as_ref().unwrap_err().to_string();
+                assert!(error.contains(&format!("{}.example", result.name)), "{}", error);
+            }
+            results.into_iter().map(|result| result.name).collect::<Vec<_>>()
+        };
+        assert_eq!(names(Severity::Debug, "boiler"), ["telemetry"]);
+        assert_eq!(names(Severity::Warning, "pump"), Vec::<String>::new());
+        assert_eq!(names(Severity::Warning, "boiler"), ["alerts"]);
+        assert_eq!(names(Severity::Critical, "boiler"), ["alerts", "telemetry"]);
+    }
+
+    #[test]
+    fn applies_silence_and_role_mentions() {
+        let packet = MessageBuilder::new("Boiler over temperature", false).build();
+        let silent = RouteRule::new("alerts").with_silent(true).apply(&packet).unwrap();
+        assert_eq!(silent.flags, MessageFlags::SUPPRESS_NOTIFICATIONS);
+        assert_eq!(silent.content, packet.content);
+
+        let mentioned = RouteRule::new("alerts").with_role_mention(112233u64).apply(&packet).unwrap();
+        assert_eq!(mentioned.content, "<@&112233> Boiler over temperature");
+        assert_eq!(mentioned.flags, 0);
+    }
+
+    #[test]
+    fn mentions_in_a_text_display_for_components_v2() {
+        let packet = MessagePacket {
+            components: vec![TextDisplay::new("Boiler over temperature").into()],
+            flags: MessageFlags::IS_COMPONENTS_V2,
+            ..MessagePacket::default()
+        };
+        let mentioned = RouteRule::new("alerts").with_role_mention(112233u64).apply(&packet).unwrap();
+        assert!(mentioned.content.is_empty());
+        assert_eq!(
+            mentioned.components,
+            [TextDisplay::new("<@&112233>").into(), TextDisplay::new("Boiler over temperature").into()]
+        );
+        mentioned.validate().unwrap();
+    }
+
+    #[test]
+    fn rejects_mentions_that_overflow_the_content() {
+        let rule = RouteRule::new("alerts").with_role_mention(112233u64);
+        let fits = MessageBuilder::new("x".repeat(CONTENT_LIMIT - 11), false).build();
+        assert_eq!(rule.apply(&fits).unwrap().content.chars().count(), CONTENT_LIMIT);
+
+        let full = MessageBuilder::new("x".repeat(CONTENT_LIMIT - 10), false).build();
+        assert_eq!(rule.apply(&full).unwrap_err().to_string(), "content is too long to mention role 112233");
+    }
+}
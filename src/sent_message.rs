@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use super::{component::Component, embed::Embed, poll::Poll, snowflake::Snowflake};
+
+/// A message as discord returns it, after sending with [super::WebhookBuilder::send_message_and_wait] or fetching with
+/// [super::WebhookBuilder::get_message].
+///
+/// Only the fields useful to a webhook are kept, the rest are ignored.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SentMessage {
+    pub id: Snowflake,
+    pub channel_id: Snowflake,
+    pub webhook_id: Option<Snowflake>,
+    pub content: String,
+    pub embeds: Vec<Embed>,
+    pub components: Vec<Component>,
+    pub poll: Option<Poll>,
+    /// When the message was sent, as an ISO8601 timestamp.
+    pub timestamp: String,
+    pub edited_timestamp: Option<String>,
+    pub flags: u64,
+}
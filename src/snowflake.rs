@@ -0,0 +1,105 @@
+use std::{fmt, num::ParseIntError, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A discord id, such as a webhook, message, user or role id.
+///
+/// Snowflakes are 64 bit, so they do not fit into a `usize` on the ESP32 and only convert from a `u64`, which also lets
+/// integer literals such as `1234` infer their type. Discord sends them as strings in JSON, which is also how they are
+/// serialized, but both strings and numbers are accepted when deserializing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Snowflake(pub u64);
+
+impl Snowflake {
+    pub const fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// Returns the raw id.
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Snowflake {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Snowflake {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+impl From<u64> for Snowflake {
+    fn from(id: u64) -> Self {
+        Self(id)
+    }
+}
+
+impl From<Snowflake> for u64 {
+    fn from(id: Snowflake) -> Self {
+        id.0
+    }
+}
+
+impl Serialize for Snowflake {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Snowflake {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SnowflakeVisitor;
+
+        impl de::Visitor<'_> for SnowflakeVisitor {
+            type Value = Snowflake;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a snowflake as a string or integer")
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(Snowflake(v))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                u64::try_from(v).map(Snowflake).map_err(E::custom)
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(SnowflakeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn into_snowflake(id: impl Into<Snowflake>) -> Snowflake {
+        id.into()
+    }
+
+    #[test]
+    fn integer_literals_convert() {
+        assert_eq!(into_snowflake(1234), Snowflake(1234));
+        assert_eq!(into_snowflake(1098765432109876543u64), Snowflake(1098765432109876543));
+    }
+
+    #[test]
+    fn deserializes_strings_and_numbers() {
+        assert_eq!(serde_json::from_str::<Snowflake>(r#""1234""#).unwrap(), Snowflake(1234));
+        assert_eq!(serde_json::from_str::<Snowflake>("1234").unwrap(), Snowflake(1234));
+        assert!(serde_json::from_str::<Snowflake>("-1").is_err());
+        assert_eq!(serde_json::to_string(&Snowflake(1234)).unwrap(), r#""1234""#);
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use esp_idf_svc::nvs::{EspNvs, NvsPartitionId};
+
+/// A small key value store used to keep state across reboots, such as the id of a [super::LiveMessage].
+///
+/// Implemented for [EspNvs], and by [MemoryStorage] for tests and host builds. Keep keys at 15 characters or less,
+/// that is the longest key NVS accepts.
+pub trait Storage {
+    /// Loads the value stored under `key`, if any.
+    fn load(&mut self, key: &str) -> anyhow::Result<Option<Vec<u8>>>;
+
+    /// Stores `value` under `key`, replacing any previous value.
+    fn store(&mut self, key: &str, value: &[u8]) -> anyhow::Result<()>;
+
+    /// Removes the value stored under `key`, if any.
+    fn remove(&mut self, key: &str) -> anyhow::Result<()>;
+}
+
+impl<T: NvsPartitionId> Storage for EspNvs<T> {
+    fn load(&mut self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let Some(len) = self.blob_len(key)? else {
+            return Ok(None);
+        };
+        let mut buf = vec![0u8; len];
+        Ok(self.get_blob(key, &mut buf)?.map(|value| value.to_vec()))
+    }
+
+    fn store(&mut self, key: &str, value: &[u8]) -> anyhow::Result<()> {
+        self.set_blob(key, value)?;
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> anyhow::Result<()> {
+        EspNvs::remove(self, key)?;
+        Ok(())
+    }
+}
+
+/// A [Storage] that only lives as long as the program, for tests and running on the host.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStorage {
+    values: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load(&mut self, key: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.values.get(key).cloned())
+    }
+
+    fn store(&mut self, key: &str, value: &[u8]) -> anyhow::Result<()> {
+        self.values.insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) -> anyhow::Result<()> {
+        self.values.remove(key);
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::io::{self, BufWriter, Read, Write};
+
+use anyhow::bail;
+use serde::Serialize;
+
+use super::attachment::Attachment;
+
+/// The size of the buffer sitting between serde and the http connection.
+///
+/// Small enough to live comfortably on an ESP32 heap, large enough that we do not issue a write per JSON token.
+const WRITE_BUFFER_SIZE: usize = 256;
+
+/// A [Write] sink that only counts how many bytes pass through it.
+///
+/// Used as a pre-pass to compute the `Content-Length` of a packet without rendering it into memory.
+#[derive(Default)]
+pub(crate) struct ByteCounter {
+    count: usize,
+}
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.count += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A [Write] sink that looks for [BOUNDARY] in what passes through it, including across writes.
+#[derive(Default)]
+struct BoundaryFinder {
+    tail: Vec<u8>,
+    found: bool,
+}
+
+impl BoundaryFinder {
+    fn contains_boundary<T: Serialize>(packet: &T) -> anyhow::Result<bool> {
+        let mut finder = Self::default();
+        serde_json::to_writer(&mut finder, packet)?;
+        Ok(finder.found)
+    }
+}
+
+impl Write for BoundaryFinder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.found {
+            return Ok(buf.len());
+        }
+        self.tail.extend_from_slice(buf);
+        self.found = self.tail.windows(BOUNDARY.len()).any(|window| window == BOUNDARY.as_bytes());
+        // Keep just enough to catch a boundary split over this write and the next.
+        let keep = self.tail.len().min(BOUNDARY.len() - 1);
+        self.tail.drain(..self.tail.len() - keep);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Adapts an [embedded_svc::io] reader or writer (such as an http request) into its [std::io] counterpart,
+/// so serde and the std helpers can work with it directly.
+pub(crate) struct EmbeddedIo<T>(pub T);
+
+impl<R: embedded_svc::io::Read> Read for EmbeddedIo<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0
+            .read(buf)
+            .map_err(|e| io::Error::other(format!("{:?}", e)))
+    }
+}
+
+impl<W: embedded_svc::io::Write> Write for EmbeddedIo<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .write(buf)
+            .map_err(|e| io::Error::other(format!("{:?}", e)))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0
+            .flush()
+            .map_err(|e| io::Error::other(format!("{:?}", e)))
+    }
+}
+
+/// Separates the parts of multipart bodies, long and random enough not to show up inside the JSON or the files.
+const BOUNDARY: &str = "diswh-esp-7d3f9a61c2b84e05";
+
+/// The body of a request: the JSON form of a packet, or a `multipart/form-data` body holding it as `payload_json`
+/// followed by the uploaded files.
+pub(crate) struct Body<'a, T> {
+    packet: &'a T,
+    files: &'a [Attachment],
+}
+
+impl<'a, T: Serialize> Body<'a, T> {
+    pub(crate) fn new(packet: &'a T, files: &'a [Attachment]) -> anyhow::Result<Self> {
+        if files.is_empty() {
+            return Ok(Self { packet, files });
+        }
+        for file in files {
+            // The fields are public, so check again what Attachment::new already checked.
+            file.validate()?;
+            if file.data.windows(BOUNDARY.len()).any(|window| window == BOUNDARY.as_bytes()) {
+                bail!("attachment {:?} holds the multipart boundary", file.filename);
+            }
+        }
+        if BoundaryFinder::contains_boundary(packet)? {
+            bail!("the message holds the multipart boundary");
+        }
+        Ok(Self { packet, files })
+    }
+
+    pub(crate) fn content_type(&self) -> String {
+        if self.files.is_empty() {
+            "application/json".to_string()
+        } else {
+            format!("multipart/form-data; boundary={}", BOUNDARY)
+        }
+    }
+
+    /// Computes the length of the body in bytes, without rendering it into memory.
+    pub(crate) fn content_length(&self) -> anyhow::Result<usize> {
+        let mut counter = ByteCounter::default();
+        self.write_parts(&mut counter)?;
+        Ok(counter.count)
+    }
+
+    /// Writes the body straight into `writer`, without building an intermediate [serde_json::Value] or [String].
+    pub(crate) fn write<W: Write>(&self, writer: W) -> anyhow::Result<()> {
+        let mut writer = BufWriter::with_capacity(WRITE_BUFFER_SIZE, writer);
+        self.write_parts(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn write_parts<W: Write>(&self, writer: &mut W) -> anyhow::Result<()> {
+        if self.files.is_empty() {
+            serde_json::to_writer(writer, self.packet)?;
+            return Ok(());
+        }
+        write!(
+            writer,
+            "--{}\r\nContent-Disposition: form-data; name=\"payload_json\"\r\nContent-Type: application/json\r\n\r\n",
+            BOUNDARY
+        )?;
+        serde_json::to_writer(&mut *writer, self.packet)?;
+        for (index, file) in self.files.iter().enumerate() {
+            write!(
+                writer,
+                "\r\n--{}\r\nContent-Disposition: form-data; name=\"files[{}]\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                BOUNDARY, index, file.filename, file.content_type
+            )?;
+            writer.write_all(&file.data)?;
+        }
+        write!(writer, "\r\n--{}--\r\n", BOUNDARY)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBuilder;
+
+    fn csv() -> Attachment {
+        Attachment::new("readings.csv", "text/csv", b"time,temperature\n0,21.5\n".to_vec())
+    }
+
+    #[test]
+    fn writes_multipart_bodies() {
+        let packet = MessageBuilder::new("Readings", false).add_file(csv()).build();
+        let body = Body::new(&packet, &packet.files).unwrap();
+        let mut written = Vec::new();
+        body.write(&mut written).unwrap();
+        assert_eq!(body.content_length().unwrap(), written.len());
+
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.starts_with(&format!("--{}\r\n", BOUNDARY)), "{}", written);
+        assert!(written.contains("name=\"files[0]\"; filename=\"readings.csv\"\r\nContent-Type: text/csv\r\n\r\ntime,"));
+        assert!(written.ends_with(&format!("\r\n--{}--\r\n", BOUNDARY)));
+    }
+
+    #[test]
+    fn rejects_the_boundary_in_the_payload() {
+        let packet = MessageBuilder::new(format!("look: {}", BOUNDARY), false).add_file(csv()).build();
+        assert!(Body::new(&packet, &packet.files).is_err());
+        // Without files there are no parts to separate.
+        let packet = MessageBuilder::new(BOUNDARY, false).build();
+        assert!(Body::new(&packet, &packet.files).is_ok());
+    }
+
+    #[test]
+    fn finds_the_boundary_across_writes() {
+        let (head, rest) = BOUNDARY.split_at(7);
+        let mut finder = BoundaryFinder::default();
+        finder.write_all(b"{\"content\":\"").unwrap();
+        finder.write_all(head.as_bytes()).unwrap();
+        assert!(!finder.found);
+        finder.write_all(rest.as_bytes()).unwrap();
+        assert!(finder.found);
+    }
+
+    #[test]
+    fn rejects_unsafe_headers_set_on_the_fields() {
+        let mut file = csv();
+        file.content_type = "text/csv\r\nX-Injected: 1".into();
+        let files = [file];
+        assert!(Body::new(&(), &files).is_err());
+
+        let mut file = csv();
+        file.filename = "a\"b.csv".into();
+        let files = [file];
+        assert!(Body::new(&(), &files).is_err());
+    }
+}
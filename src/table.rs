@@ -0,0 +1,358 @@
+use std::fmt::Display;
+
+use super::{markdown, util::truncate, Embed, EmbedBuilder};
+
+/// The most fields discord accepts on an embed.
+const FIELD_LIMIT: usize = 25;
+/// The most characters discord accepts in a field's name.
+const FIELD_NAME_LIMIT: usize = 256;
+/// The most characters discord accepts in a field's value.
+const FIELD_VALUE_LIMIT: usize = 1024;
+/// The most characters discord accepts across an embed.
+const EMBED_TOTAL_LIMIT: usize = 6000;
+
+/// How the cells of a column are aligned in a code block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alignment {
+    Left,
+    Right,
+}
+
+/// Rows of readings, rendered either as aligned code blocks or as a grid of embed fields.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{EmbedBuilder, Table};
+///
+/// let table = Table::new(["Sensor", "Value", "Unit", "Status"])
+///     .add_row(["Boiler", "72.5", "°C", "OK"])
+///     .add_row(["Return", "48", "°C", "OK"]);
+///
+/// assert_eq!(
+///     table.to_code_blocks(2000),
+///     ["```\nSensor  Value  Unit  Status\n------  -----  ----  ------\nBoiler   72.5  °C    OK\nReturn     48  °C    OK\n```"]
+/// );
+///
+/// let embeds = table.to_embeds(&EmbedBuilder::new().with_title("Heating"));
+/// assert_eq!(embeds[0].fields[0].name, "Boiler");
+/// assert_eq!(embeds[0].fields[0].value, "Value: 72.5\nUnit: °C\nStatus: OK");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    alignments: Vec<Option<Alignment>>,
+    max_width: Option<usize>,
+}
+
+impl Table {
+    /// Constructs an empty table with the given column headers.
+    ///
+    /// # Panics
+    /// Will panic if there are no headers, or any of them can not be converted into a [String].
+    pub fn new<T: Into<String>>(headers: impl IntoIterator<Item = T>) -> Self {
+        let headers: Vec<String> = headers.into_iter().map(Into::into).collect();
+        assert!(!headers.is_empty(), "a table needs at least one column");
+        Self {
+            alignments: vec![None; headers.len()],
+            headers,
+            rows: Vec::new(),
+            max_width: Some(60),
+        }
+    }
+
+    /// Adds a row, missing cells are left empty.
+    ///
+    /// # Panics
+    /// Will panic if the row has more cells than the table has columns.
+    pub fn add_row<T: Display>(mut self, cells: impl IntoIterator<Item = T>) -> Self {
+        let mut row: Vec<String> = cells.into_iter().map(|cell| cell.to_string()).collect();
+        assert!(
+            row.len() <= self.headers.len(),
+            "a row has at most {} cells",
+            self.headers.len()
+        );
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+        self
+    }
+
+    /// Sets the alignment of a column in code blocks.
+    ///
+    /// By default columns holding only numbers are aligned right, others left.
+    ///
+    /// # Panics
+    /// Will panic if the table has no such column.
+    pub fn with_alignment(mut self, column: usize, alignment: Alignment) -> Self {
+        self.alignments[column] = Some(alignment);
+        self
+    }
+
+    /// Sets the most characters a code block line may take, defaults to 60 which fits most phone screens.
+    ///
+    /// The widest columns are shortened first, cutting their cells off with `…`. [None] never shortens columns.
+    pub fn with_max_width(mut self, max_width: Option<usize>) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Renders the table as monospaced, column aligned code blocks.
+    ///
+    /// Each block holds at most `limit` characters, such as 2000 for message content or 4096 for an embed description,
+    /// and repeats the header when the table is split.
+    ///
+    /// # Panics
+    /// Will panic if `limit` can not hold the header and a single row.
+    pub fn to_code_blocks(&self, limit: usize) -> Vec<String> {
+        let widths = self.widths();
+        let header = self.line(&self.headers, &widths);
+        let rule = self.line(
+            &widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>(),
+            &widths,
+        );
+        let head = format!("```\n{}\n{}\n", header, rule);
+        let fence = "```";
+
+        let mut blocks = Vec::new();
+        let mut block = head.clone();
+        let mut block_length = head.chars().count();
+        let mut block_rows = 0;
+        for row in &self.rows {
+            let line = format!("{}\n", self.line(row, &widths));
+            let line_length = line.chars().count();
+            if block_length + line_length + fence.len() > limit {
+                assert!(block_rows > 0, "a code block of {} characters can not hold a single row", limit);
+                blocks.push(block + fence);
+                block = head.clone();
+                block_length = head.chars().count();
+                block_rows = 0;
+            }
+            block += &line;
+            block_length += line_length;
+            block_rows += 1;
+        }
+        blocks.push(block + fence);
+        blocks
+    }
+
+    /// Renders the table as a grid of inline embed fields, three to a line.
+    ///
+    /// Every row becomes a field named after its first cell, listing the other cells with their headers. Each embed
+    /// starts as a copy of `template`, such as a builder with a title and color, and holds up to 25 fields and 6000
+    /// characters; the rest continue in further embeds.
+    ///
+    /// Large tables can return more than the 10 embeds a message holds, send those as several messages:
+    /// ```
+    /// use diswh_esp::{EmbedBuilder, MessageBuilder, Table};
+    ///
+    /// let table = (0..300).fold(Table::new(["Sensor", "Value"]), |table, i| table.add_row([i, i * 2]));
+    /// let embeds = table.to_embeds(&EmbedBuilder::new());
+    /// assert_eq!(embeds.len(), 12);
+    ///
+    /// for chunk in embeds.chunks(10) {
+    ///     let packet = chunk
+    ///         .iter()
+    ///         .fold(MessageBuilder::new("", false), |message, embed| message.add_embed(embed.clone()))
+    ///         .build();
+    ///     assert!(packet.embeds.len() <= 10);
+    /// }
+    /// ```
+    pub fn to_embeds(&self, template: &EmbedBuilder) -> Vec<Embed> {
+        let base = template.clone().build();
+        let base_size = embed_size(&base);
+
+        let mut embeds = Vec::new();
+        let mut builder = template.clone();
+        let mut fields = 0;
+        let mut size = base_size;
+        for row in &self.rows {
+            let name = truncate(&markdown::escape(&row[0]), FIELD_NAME_LIMIT).to_string();
+            let value = self
+                .headers
+                .iter()
+                .zip(row)
+                .skip(1)
+                .map(|(header, cell)| format!("{}: {}", markdown::escape(header), markdown::escape(cell)))
+                .collect::<Vec<_>>()
+                .join("\n");
+            // Discord rejects empty fields, a zero width space stands in for them.
+            let name = if name.is_empty() { "\u{200b}".to_string() } else { name };
+            let value = match truncate(&value, FIELD_VALUE_LIMIT) {
+                "" => "\u{200b}".to_string(),
+                value => value.to_string(),
+            };
+
+            let field_size = name.chars().count() + value.chars().count();
+            if fields == FIELD_LIMIT || (fields > 0 && size + field_size > EMBED_TOTAL_LIMIT) {
+                embeds.push(builder.build());
+                builder = template.clone();
+                fields = 0;
+                size = base_size;
+            }
+            builder = builder.add_field(name, value, true);
+            fields += 1;
+            size += field_size;
+        }
+        embeds.push(builder.build());
+        embeds
+    }
+
+    /// The width of every column as written in a code block, shortened to fit the maximum width.
+    fn widths(&self) -> Vec<usize> {
+        let width = |cell: &str| markdown::escape_code_block(cell).chars().count();
+        let mut widths: Vec<usize> = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(column, header)| {
+                self.rows
+                    .iter()
+                    .map(|row| width(&row[column]))
+                    .chain([width(header)])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        if let Some(max_width) = self.max_width {
+            let gaps = 2 * widths.len().saturating_sub(1);
+            while widths.iter().sum::<usize>() + gaps > max_width {
+                let Some(widest) = widths.iter_mut().filter(|width| **width > 1).max() else {
+                    break;
+                };
+                *widest -= 1;
+            }
+        }
+        widths
+    }
+
+    fn alignment(&self, column: usize) -> Alignment {
+        self.alignments[column].unwrap_or_else(|| {
+            let numeric = self
+                .rows
+                .iter()
+                .map(|row| row[column].trim())
+                .filter(|cell| !cell.is_empty())
+                .all(|cell| cell.parse::<f64>().is_ok());
+            if numeric && !self.rows.is_empty() {
+                Alignment::Right
+            } else {
+                Alignment::Left
+            }
+        })
+    }
+
+    fn line(&self, cells: &[String], widths: &[usize]) -> String {
+        let cells: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, width))| {
+                let cell = fit(&markdown::escape_code_block(cell), *width);
+                match self.alignment(column) {
+                    Alignment::Left => format!("{:<width$}", cell, width = width),
+                    Alignment::Right => format!("{:>width$}", cell, width = width),
+                }
+            })
+            .collect();
+        cells.join("  ").trim_end().to_string()
+    }
+}
+
+/// Cuts `cell` down to `width` characters, ending in `…` when it was cut.
+fn fit(cell: &str, width: usize) -> String {
+    if cell.chars().count() <= width {
+        return cell.to_string();
+    }
+    cell.chars().take(width.saturating_sub(1)).chain(['…']).collect()
+}
+
+/// Counts the characters discord counts towards the total limit of an embed.
+fn embed_size(embed: &Embed) -> usize {
+    let text = |text: &Option<String>| text.as_deref().map_or(0, |text| text.chars().count());
+    text(&embed.title)
+        + text(&embed.description)
+        + text(&embed.footer.text)
+        + text(&embed.author.name)
+        + embed
+            .fields
+            .iter()
+            .map(|field| field.name.chars().count() + field.value.chars().count())
+            .sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "a table needs at least one column")]
+    fn rejects_tables_without_columns() {
+        Table::new(Vec::<String>::new());
+    }
+
+    #[test]
+    fn single_column_rows_become_fields() {
+        let embeds = Table::new(["Sensor"]).add_row(["Boiler"]).add_row::<&str>([]).to_embeds(&EmbedBuilder::new());
+        let fields: Vec<(&str, &str)> = embeds[0]
+            .fields
+            .iter()
+            .map(|field| (field.name.as_str(), field.value.as_str()))
+            .collect();
+        assert_eq!(fields, [("Boiler", "\u{200b}"), ("\u{200b}", "\u{200b}")]);
+    }
+
+    #[test]
+    fn splits_embeds_at_the_field_limit() {
+        let table = (0..30).fold(Table::new(["Sensor", "Value"]), |table, i| table.add_row([i.to_string(), "1".into()]));
+        let embeds = table.to_embeds(&EmbedBuilder::new().with_title("Readings"));
+        assert_eq!(embeds.len(), 2);
+        assert_eq!(embeds[0].fields.len(), FIELD_LIMIT);
+        assert_eq!(embeds[1].fields.len(), 5);
+        assert_eq!(embeds[1].title.as_deref(), Some("Readings"));
+    }
+
+    #[test]
+    fn splits_embeds_at_the_total_limit() {
+        let value = "x".repeat(1000);
+        let table = (0..12).fold(Table::new(["Sensor", "Value"]), |table, i| {
+            table.add_row([format!("Sensor {:02}", i), value.clone()])
+        });
+        let template = EmbedBuilder::new().with_title("Readings");
+        let embeds = table.to_embeds(&template);
+        // Every field takes 9 + 7 + 1000 characters, so 5 of them fit beside the title.
+        let fields: Vec<usize> = embeds.iter().map(|embed| embed.fields.len()).collect();
+        assert_eq!(fields, [5, 5, 2]);
+        for embed in &embeds {
+            assert!(embed_size(embed) <= EMBED_TOTAL_LIMIT, "{}", embed_size(embed));
+            assert_eq!(embed.title.as_deref(), Some("Readings"));
+        }
+        assert_eq!(embeds[1].fields[0].name, "Sensor 05");
+    }
+
+    #[test]
+    fn aligns_cells_holding_code_fences() {
+        let table = Table::new(["Cell", "Next"])
+            .add_row(["```", "a"])
+            .add_row(["b", "c"]);
+        let block = &table.to_code_blocks(2000)[0];
+        let lines: Vec<&str> = block.lines().skip(1).collect();
+        assert_eq!(lines[0], "Cell   Next");
+        assert_eq!(lines[1], "-----  ----");
+        assert_eq!(lines[2], "`\u{200b}`\u{200b}`  a");
+        assert_eq!(lines[3], "b      c");
+    }
+
+    #[test]
+    fn splits_code_blocks_and_repeats_the_header() {
+        let table = Table::new(["A"]).add_row(["1"]).add_row(["2"]);
+        assert_eq!(table.to_code_blocks(14), ["```\nA\n-\n1\n```", "```\nA\n-\n2\n```"]);
+    }
+
+    #[test]
+    fn shortens_the_widest_columns() {
+        let table = Table::new(["Name", "Value"])
+            .add_row(["A very long sensor name", "1"])
+            .with_max_width(Some(16));
+        assert_eq!(table.to_code_blocks(2000), ["```\nName       Value\n---------  -----\nA very l…      1\n```"]);
+    }
+}
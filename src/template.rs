@@ -0,0 +1,278 @@
+use std::{collections::HashMap, fmt::Display, marker::PhantomData};
+
+use anyhow::{anyhow, bail};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use super::{Embed, MessagePacket};
+
+/// A template for a [MessagePacket].
+pub type MessageTemplate = Template<MessagePacket>;
+/// A template for an [Embed].
+pub type EmbedTemplate = Template<Embed>;
+
+/// A message or embed skeleton with `{placeholders}` in its string fields, rendered with different values each time.
+///
+/// Any string field can hold placeholders, including embed fields, footers and component labels. Write `{{` and `}}`
+/// for literal braces. Rendering fails when a placeholder has no value, rather than sending a half filled message.
+///
+/// Templates can be built in code, or loaded from JSON so the formatting can be changed through config without
+/// reflashing the device. Files are not part of a template, add them to the rendered message instead.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{EmbedBuilder, MessageBuilder, MessageTemplate};
+///
+/// let template = MessageTemplate::new(
+///     MessageBuilder::new("", false)
+///         .add_embed(
+///             EmbedBuilder::new()
+///                 .with_title("{sensor}")
+///                 .add_field("Temperature", "{temperature} °C", true)
+///                 .build(),
+///         )
+///         .build(),
+/// )?;
+///
+/// let packet = template.render([("sensor", "Greenhouse"), ("temperature", "21.4")])?;
+/// assert_eq!(packet.embeds[0].title.as_deref(), Some("Greenhouse"));
+/// assert_eq!(packet.embeds[0].fields[0].value, "21.4 °C");
+///
+/// assert!(template.render([("sensor", "Greenhouse")]).is_err());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Template<T> {
+    skeleton: Value,
+    placeholders: Vec<String>,
+    packet: PhantomData<fn() -> T>,
+}
+
+impl<T: Serialize + DeserializeOwned> Template<T> {
+    /// Constructs a template from a skeleton, failing when a placeholder is malformed or the skeleton holds files.
+    pub fn new(skeleton: T) -> anyhow::Result<Self> {
+        let skeleton = serde_json::to_value(skeleton)?;
+        // Fields that are never deserialized, such as the files of a message, would be dropped by every render.
+        if serde_json::to_value(serde_json::from_value::<T>(skeleton.clone())?)? != skeleton {
+            bail!("templates can not hold files, add them to the rendered message instead");
+        }
+        Self::from_value(skeleton)
+    }
+
+    /// Loads a template from JSON, such as a config file, failing when a placeholder is malformed.
+    ///
+    /// # Example
+    /// ```
+    /// use diswh_esp::EmbedTemplate;
+    ///
+    /// let template = EmbedTemplate::from_json(r#"{"title": "{{{device}}} offline", "color": 15548997}"#)?;
+    /// let embed = template.render([("device", "pump-3")])?;
+    /// assert_eq!(embed.title.as_deref(), Some("{pump-3} offline"));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        // Parse into the target type first so a typo in a field name fails here instead of on every render.
+        let skeleton: T = serde_json::from_str(json)?;
+        Self::new(skeleton)
+    }
+
+    fn from_value(skeleton: Value) -> anyhow::Result<Self> {
+        let mut placeholders = Vec::new();
+        walk(&mut skeleton.clone(), &mut |key| {
+            if !placeholders.iter().any(|known| known == key) {
+                placeholders.push(key.to_string());
+            }
+            Ok(String::new())
+        })?;
+        Ok(Self {
+            skeleton,
+            placeholders,
+            packet: PhantomData,
+        })
+    }
+
+    /// The names of all placeholders in the template, in the order they first appear.
+    pub fn placeholders(&self) -> &[String] {
+        &self.placeholders
+    }
+
+    /// Renders the template with values from key value pairs, such as a [HashMap] or an array of tuples.
+    pub fn render<K: AsRef<str>, V: Display>(&self, values: impl IntoIterator<Item = (K, V)>) -> anyhow::Result<T> {
+        let values: HashMap<String, String> = values
+            .into_iter()
+            .map(|(key, value)| (key.as_ref().to_string(), value.to_string()))
+            .collect();
+        self.render_lookup(|key| values.get(key).cloned())
+    }
+
+    /// Renders the template with the fields of a serializable struct.
+    ///
+    /// Nested fields are reached with dots, as `{reading.temperature}`, and list items by index, as `{readings.0}`.
+    /// Field names holding dots themselves can not be reached. Strings are inserted as is, `null` as an empty string
+    /// and other values as JSON.
+    ///
+    /// # Example
+    /// ```
+    /// use diswh_esp::EmbedTemplate;
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct Reading {
+    ///     sensor: &'static str,
+    ///     temperature: f32,
+    /// }
+    ///
+    /// let template = EmbedTemplate::from_json(r#"{"description": "{sensor}: {temperature} °C"}"#)?;
+    /// let embed = template.render_with(&Reading { sensor: "Boiler", temperature: 72.5 })?;
+    /// assert_eq!(embed.description.as_deref(), Some("Boiler: 72.5 °C"));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn render_with<S: Serialize>(&self, values: &S) -> anyhow::Result<T> {
+        let values = serde_json::to_value(values)?;
+        self.render_lookup(|key| {
+            // Escape the characters JSON pointers treat specially, `~` first so its escape is not escaped again.
+            let pointer = key.replace('~', "~0").replace('/', "~1").replace('.', "/");
+            values
+                .pointer(&format!("/{}", pointer))
+                .map(|value| match value {
+                    Value::String(text) => text.clone(),
+                    Value::Null => String::new(),
+                    value => value.to_string(),
+                })
+        })
+    }
+
+    fn render_lookup(&self, lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<T> {
+        let mut rendered = self.skeleton.clone();
+        walk(&mut rendered, &mut |key| {
+            lookup(key).ok_or_else(|| anyhow!("no value for placeholder `{}`", key))
+        })?;
+        Ok(serde_json::from_value(rendered)?)
+    }
+}
+
+/// Substitutes the placeholders of every string in `value`, object keys are left alone.
+fn walk(value: &mut Value, lookup: &mut dyn FnMut(&str) -> anyhow::Result<String>) -> anyhow::Result<()> {
+    match value {
+        Value::String(text) => *text = substitute(text, lookup)?,
+        Value::Array(items) => {
+            for item in items {
+                walk(item, lookup)?;
+            }
+        }
+        Value::Object(fields) => {
+            for field in fields.values_mut() {
+                walk(field, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn substitute(text: &str, lookup: &mut dyn FnMut(&str) -> anyhow::Result<String>) -> anyhow::Result<String> {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(position) = rest.find(['{', '}']) {
+        rendered += &rest[..position];
+        let tail = &rest[position..];
+        if let Some(after) = tail.strip_prefix("{{") {
+            rendered.push('{');
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("}}") {
+            rendered.push('}');
+            rest = after;
+        } else if tail.starts_with('}') {
+            bail!("unmatched `}}` in {:?}, write `}}}}` for a literal brace", text);
+        } else {
+            let Some(end) = tail.find('}') else {
+                bail!("unclosed placeholder in {:?}, write `{{{{` for a literal brace", text);
+            };
+            let key = tail[1..end].trim();
+            if key.is_empty() || key.contains('{') {
+                bail!("invalid placeholder {:?} in {:?}", &tail[..=end], text);
+            }
+            rendered += &lookup(key)?;
+            rest = &tail[end + 1..];
+        }
+    }
+    rendered += rest;
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{Attachment, EmbedTemplate, MessageBuilder};
+
+    fn render(text: &str) -> anyhow::Result<String> {
+        substitute(text, &mut |key| Ok(format!("<{}>", key)))
+    }
+
+    #[test]
+    fn substitutes_placeholders_and_escaped_braces() {
+        assert_eq!(render("{a} and { b }").unwrap(), "<a> and <b>");
+        assert_eq!(render("{{literal}} {{{a}}}").unwrap(), "{literal} {<a>}");
+        assert_eq!(render("no placeholders").unwrap(), "no placeholders");
+        assert_eq!(render("").unwrap(), "");
+    }
+
+    #[test]
+    fn unmatched_closing_brace_fails() {
+        let error = render("a } b").unwrap_err().to_string();
+        assert!(error.starts_with("unmatched `}`"), "{}", error);
+        assert!(render("{a}}").is_err());
+    }
+
+    #[test]
+    fn unclosed_placeholder_fails() {
+        let error = render("temperature {value").unwrap_err().to_string();
+        assert!(error.starts_with("unclosed placeholder"), "{}", error);
+        assert!(render("{").is_err());
+    }
+
+    #[test]
+    fn empty_placeholder_fails() {
+        assert!(render("{}").unwrap_err().to_string().starts_with("invalid placeholder \"{}\""));
+        assert!(render("{   }").is_err());
+    }
+
+    #[test]
+    fn nested_braces_fail() {
+        let error = render("{outer {inner}}").unwrap_err().to_string();
+        assert!(error.starts_with("invalid placeholder \"{outer {inner}\""), "{}", error);
+    }
+
+    #[test]
+    fn render_with_escapes_pointer_characters() {
+        let template = EmbedTemplate::from_json(r#"{"description": "{a/b} {c~d} {nested.e/f} {list.1}"}"#).unwrap();
+        let values = json!({
+            "a/b": "slash",
+            "c~d": "tilde",
+            "nested": { "e/f": "deep" },
+            "list": ["zero", 1],
+            // Unescaped, `{c~d}` would look up `c~d` literally and `{a/b}` would find this instead.
+            "a": { "b": "wrong" }
+        });
+        let embed = template.render_with(&values).unwrap();
+        assert_eq!(embed.description.as_deref(), Some("slash tilde deep 1"));
+
+        let template = EmbedTemplate::from_json(r#"{"description": "{c~1d}"}"#).unwrap();
+        assert!(template.render_with(&json!({ "c/d": "escaped twice" })).is_err());
+        assert_eq!(
+            template.render_with(&json!({ "c~1d": "as written" })).unwrap().description.as_deref(),
+            Some("as written")
+        );
+    }
+
+    #[test]
+    fn skeletons_with_files_are_rejected() {
+        let packet = MessageBuilder::new("{sensor}", false)
+            .add_file(Attachment::new("log.txt", "text/plain", b"log".to_vec()))
+            .build();
+        let error = MessageTemplate::new(packet).unwrap_err().to_string();
+        assert_eq!(error, "templates can not hold files, add them to the rendered message instead");
+        assert!(MessageTemplate::new(MessageBuilder::new("{sensor}", false).build()).is_ok());
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    fmt,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How a [Timestamp] is shown, each reader sees it in their own time zone and language.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TimestampStyle {
+    /// `16:20`
+    ShortTime,
+    /// `16:20:30`
+    LongTime,
+    /// `20/04/2021`
+    ShortDate,
+    /// `20 April 2021`
+    LongDate,
+    /// `20 April 2021 16:20`
+    #[default]
+    ShortDateTime,
+    /// `Tuesday, 20 April 2021 16:20`
+    LongDateTime,
+    /// `2 months ago`, kept up to date by the client.
+    Relative,
+}
+
+impl TimestampStyle {
+    fn flag(self) -> char {
+        match self {
+            Self::ShortTime => 't',
+            Self::LongTime => 'T',
+            Self::ShortDate => 'd',
+            Self::LongDate => 'D',
+            Self::ShortDateTime => 'f',
+            Self::LongDateTime => 'F',
+            Self::Relative => 'R',
+        }
+    }
+}
+
+/// A point in time formatted by the discord client, written as `<t:1618953630:R>` with [Display](fmt::Display).
+///
+/// The device only needs to know the unix time, discord takes care of time zones and formatting. The relative style
+/// suits "last seen" fields, as it keeps counting up without the message being edited.
+///
+/// # Example
+/// ```
+/// use diswh_esp::{Timestamp, TimestampStyle};
+///
+/// let last_seen = Timestamp::from_unix(1618953630).with_style(TimestampStyle::Relative);
+/// assert_eq!(last_seen.to_string(), "<t:1618953630:R>");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Timestamp {
+    pub unix: i64,
+    pub style: TimestampStyle,
+}
+
+impl Timestamp {
+    /// A timestamp at `unix` seconds since the unix epoch, in the default short date and time style.
+    pub fn from_unix(unix: i64) -> Self {
+        Self {
+            unix,
+            style: TimestampStyle::default(),
+        }
+    }
+
+    /// A timestamp at `time`, rounded down to the second before it, also for times before 1970.
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let unix = match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(e) => {
+                let before = e.duration();
+                -(before.as_secs() as i64) - i64::from(before.subsec_nanos() > 0)
+            }
+        };
+        Self::from_unix(unix)
+    }
+
+    /// The current time.
+    ///
+    /// The system clock must be set, for example with `esp_idf_svc::sntp`, otherwise this is shortly after 1970.
+    pub fn now() -> Self {
+        Self::from_system_time(SystemTime::now())
+    }
+
+    /// The time `duration` ago, such as the time of the last reading.
+    pub fn ago(duration: Duration) -> Self {
+        Self::from_system_time(SystemTime::now() - duration)
+    }
+
+    /// Sets how the timestamp is shown.
+    pub fn with_style(mut self, style: TimestampStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<t:{}:{}>", self.unix, self.style.flag())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_style_has_its_flag() {
+        let styles = [
+            (TimestampStyle::ShortTime, "<t:1618953630:t>"),
+            (TimestampStyle::LongTime, "<t:1618953630:T>"),
+            (TimestampStyle::ShortDate, "<t:1618953630:d>"),
+            (TimestampStyle::LongDate, "<t:1618953630:D>"),
+            (TimestampStyle::ShortDateTime, "<t:1618953630:f>"),
+            (TimestampStyle::LongDateTime, "<t:1618953630:F>"),
+            (TimestampStyle::Relative, "<t:1618953630:R>"),
+        ];
+        for (style, expected) in styles {
+            assert_eq!(Timestamp::from_unix(1618953630).with_style(style).to_string(), expected);
+        }
+        assert_eq!(Timestamp::from_unix(1618953630).to_string(), "<t:1618953630:f>");
+    }
+
+    #[test]
+    fn negative_timestamps() {
+        assert_eq!(Timestamp::from_unix(-86400).to_string(), "<t:-86400:f>");
+    }
+
+    #[test]
+    fn from_system_time_rounds_down() {
+        let at = |since: Duration| Timestamp::from_system_time(UNIX_EPOCH + since).unix;
+        let before = |until: Duration| Timestamp::from_system_time(UNIX_EPOCH - until).unix;
+        assert_eq!(at(Duration::ZERO), 0);
+        assert_eq!(at(Duration::from_millis(1618953630_900)), 1618953630);
+        assert_eq!(before(Duration::from_secs(86400)), -86400);
+        assert_eq!(before(Duration::from_millis(500)), -1);
+        assert_eq!(before(Duration::from_millis(86400_500)), -86401);
+    }
+}
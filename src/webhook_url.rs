@@ -0,0 +1,368 @@
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, bail};
+
+use super::snowflake::Snowflake;
+
+/// The hosts discord serves webhooks from.
+const DISCORD_HOSTS: [&str; 6] = [
+    "discord.com",
+    "discordapp.com",
+    "canary.discord.com",
+    "ptb.discord.com",
+    "canary.discordapp.com",
+    "ptb.discordapp.com",
+];
+
+/// A parsed discord webhook url, split into its id, token and query parameters.
+///
+/// Both the [fmt::Display] and [fmt::Debug] output replace the token with `[redacted]`, so a [WebhookUrl] is safe to log.
+/// Use [WebhookUrl::token] if you really need the secret.
+///
+/// # Example
+/// ```
+/// use diswh_esp::WebhookUrl;
+///
+/// let url: WebhookUrl = "https://discord.com/api/webhooks/1234/secret-token/?thread_id=5678".parse().unwrap();
+/// assert_eq!(url.id().get(), 1234);
+/// assert_eq!(url.token(), "secret-token");
+/// assert_eq!(url.query("thread_id"), Some("5678"));
+/// assert_eq!(url.to_string(), "https://discord.com/api/webhooks/1234/[redacted]?thread_id=5678");
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct WebhookUrl {
+    host: String,
+    api_base: Option<String>,
+    api_version: Option<u8>,
+    id: Snowflake,
+    token: String,
+    query: Vec<(String, String)>,
+}
+
+impl WebhookUrl {
+    /// Parses a webhook url as copied from the discord client.
+    ///
+    /// Accepts the `discord.com`, `discordapp.com`, `canary` and `ptb` hosts, with or without an api version (`/api/v10/`),
+    /// a trailing slash and query parameters.
+    pub fn parse(url: &str) -> anyhow::Result<Self> {
+        let rest = url
+            .trim()
+            .strip_prefix("https://")
+            .ok_or_else(|| anyhow!("webhook url must start with https://"))?;
+        let (host, rest) = rest.split_once('/').unwrap_or((rest, ""));
+        let host = host.to_ascii_lowercase();
+        if !DISCORD_HOSTS.contains(&host.as_str()) {
+            bail!("{} is not a discord host", host);
+        }
+
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+        if segments.next() != Some("api") {
+            bail!("webhook url path must start with /api");
+        }
+        let mut segment = segments.next();
+        let api_version = match segment.and_then(|s| s.strip_prefix('v')) {
+            Some(version) => {
+                segment = segments.next();
+                Some(version.parse().map_err(|_| anyhow!("invalid api version v{}", version))?)
+            }
+            None => None,
+        };
+        if segment != Some("webhooks") {
+            bail!("webhook url path must contain /webhooks");
+        }
+        let id: Snowflake = segments
+            .next()
+            .ok_or_else(|| anyhow!("webhook url is missing the webhook id"))?
+            .parse()
+            .map_err(|_| anyhow!("webhook id is not a valid snowflake"))?;
+        let token = segments
+            .next()
+            .ok_or_else(|| anyhow!("webhook url is missing the webhook token"))?;
+        if segments.next().is_some() {
+            bail!("webhook url has unexpected trailing path segments");
+        }
+
+        let mut url = Self::from_parts(id, token);
+        url.host = host;
+        url.api_version = api_version;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            url = url.with_query(percent_decode(key), percent_decode(value));
+        }
+        Ok(url)
+    }
+
+    /// Constructs a webhook url on `discord.com` from the webhook id and token.
+    ///
+    /// # Panics
+    /// Will panic if the provided `token` can not be converted into a [String]
+    pub fn from_parts(id: impl Into<Snowflake>, token: impl Into<String>) -> Self {
+        Self {
+            host: "discord.com".to_string(),
+            api_base: None,
+            api_version: None,
+            id: id.into(),
+            token: token.into(),
+            query: Vec::new(),
+        }
+    }
+
+    /// Sets a query parameter that is sent with every request, such as `thread_id`.
+    ///
+    /// Replaces the value if the parameter is already set. `key` and `value` are percent-encoded when sent.
+    pub fn with_query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        let value = value.into();
+        match self.query.iter_mut().find(|(k, _)| *k == key) {
+            Some(pair) => pair.1 = value,
+            None => self.query.push((key, value)),
+        }
+        self
+    }
+
+    /// Sends requests to `api_base` instead of `https://{host}/api`, for example a local mock server or a relay.
+    ///
+    /// `api_base` is everything before `/webhooks/{id}/{token}`, such as `http://192.168.1.20:8080/api`.
+    /// The api version set with [WebhookUrl::with_api_version] is still appended to it.
+    pub fn with_api_base(mut self, api_base: impl Into<String>) -> Self {
+        let api_base: String = api_base.into();
+        self.api_base = Some(api_base.trim_end_matches('/').to_string());
+        self
+    }
+
+    /// Pins the requests to a specific discord api version, such as `10` for `/api/v10/`.
+    ///
+    /// `None` uses whatever version discord currently defaults to.
+    pub fn with_api_version(mut self, api_version: Option<u8>) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
+    /// Returns the value of a query parameter, if set.
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The webhook id.
+    pub fn id(&self) -> Snowflake {
+        self.id
+    }
+
+    /// The webhook token.
+    ///
+    /// # Warning
+    /// This is a secret, anyone who has it can post to your channel.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// The host the url points at, such as `discord.com`.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The api base set with [WebhookUrl::with_api_base], if any.
+    pub fn api_base(&self) -> Option<&str> {
+        self.api_base.as_deref()
+    }
+
+    /// The api version in the url, if one was given.
+    pub fn api_version(&self) -> Option<u8> {
+        self.api_version
+    }
+
+    /// Builds the full url of an endpoint below this webhook, such as `/messages/{id}`.
+    ///
+    /// `extra_query` is appended after the query parameters of the url itself.
+    pub(crate) fn endpoint(&self, path: &str, extra_query: &[(&str, &str)]) -> String {
+        self.build(&self.token, path, extra_query)
+    }
+
+    /// Like [WebhookUrl::endpoint], with the token redacted for logging.
+    pub(crate) fn redacted_endpoint(&self, path: &str, extra_query: &[(&str, &str)]) -> String {
+        self.build("[redacted]", path, extra_query)
+    }
+
+    fn build(&self, token: &str, path: &str, extra_query: &[(&str, &str)]) -> String {
+        let mut url = match &self.api_base {
+            Some(api_base) => api_base.clone(),
+            None => format!("https://{}/api", self.host),
+        };
+        if let Some(version) = self.api_version {
+            url += &format!("/v{}", version);
+        }
+        url += &format!("/webhooks/{}/{}{}", self.id, token, path);
+        let pairs = self
+            .query
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain(extra_query.iter().copied());
+        for (i, (key, value)) in pairs.enumerate() {
+            url.push(if i == 0 { '?' } else { '&' });
+            url += &percent_encode(key);
+            if !value.is_empty() {
+                url.push('=');
+                url += &percent_encode(value);
+            }
+        }
+        url
+    }
+}
+
+/// Encodes everything but the unreserved characters of RFC 3986, so `text` can be used as a query key or value.
+fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded += &format!("%{:02X}", byte),
+        }
+    }
+    encoded
+}
+
+/// Decodes `%XX` escapes, leaving malformed ones as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+impl FromStr for WebhookUrl {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.redacted_endpoint("", &[]))
+    }
+}
+
+impl fmt::Debug for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookUrl")
+            .field("host", &self.host)
+            .field("api_base", &self.api_base)
+            .field("api_version", &self.api_version)
+            .field("id", &self.id)
+            .field("token", &"[redacted]")
+            .field("query", &self.query)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(url: &str) -> WebhookUrl {
+        WebhookUrl::parse(url).unwrap()
+    }
+
+    #[test]
+    fn parses_plain_url() {
+        let url = parse("https://discord.com/api/webhooks/1234/token");
+        assert_eq!(url.host(), "discord.com");
+        assert_eq!(url.id(), Snowflake(1234));
+        assert_eq!(url.token(), "token");
+        assert_eq!(url.api_version(), None);
+        assert_eq!(url.endpoint("", &[]), "https://discord.com/api/webhooks/1234/token");
+    }
+
+    #[test]
+    fn accepts_other_hosts_versions_and_trailing_slash() {
+        let url = parse("  https://Canary.DiscordApp.com/api/v10/webhooks/1234/token/  ");
+        assert_eq!(url.host(), "canary.discordapp.com");
+        assert_eq!(url.api_version(), Some(10));
+        assert_eq!(url.endpoint("/messages/5", &[]), "https://canary.discordapp.com/api/v10/webhooks/1234/token/messages/5");
+        for host in DISCORD_HOSTS {
+            assert_eq!(parse(&format!("https://{}/api/webhooks/1/t", host)).host(), host);
+        }
+    }
+
+    #[test]
+    fn parses_query() {
+        let url = parse("https://discord.com/api/webhooks/1234/token?thread_id=5678&wait&name=a%20b");
+        assert_eq!(url.query("thread_id"), Some("5678"));
+        assert_eq!(url.query("wait"), Some(""));
+        assert_eq!(url.query("name"), Some("a b"));
+        assert_eq!(url.query("missing"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_urls() {
+        for url in [
+            "",
+            "http://discord.com/api/webhooks/1234/token",
+            "https://example.com/api/webhooks/1234/token",
+            "https://discord.com.evil.com/api/webhooks/1234/token",
+            "https://discord.com/webhooks/1234/token",
+            "https://discord.com/api/vX/webhooks/1234/token",
+            "https://discord.com/api/channels/1234/token",
+            "https://discord.com/api/webhooks/abc/token",
+            "https://discord.com/api/webhooks/1234",
+            "https://discord.com/api/webhooks/1234/token/extra",
+        ] {
+            assert!(WebhookUrl::parse(url).is_err(), "{:?} should be rejected", url);
+        }
+    }
+
+    #[test]
+    fn builds_endpoints_with_base_version_and_query() {
+        let url = WebhookUrl::from_parts(1234u64, "token")
+            .with_api_base("http://192.168.1.20:8080/api/")
+            .with_api_version(Some(10))
+            .with_query("thread_id", "1")
+            .with_query("thread_id", "2");
+        assert_eq!(
+            url.endpoint("/messages/5", &[("wait", "true")]),
+            "http://192.168.1.20:8080/api/v10/webhooks/1234/token/messages/5?thread_id=2&wait=true"
+        );
+    }
+
+    #[test]
+    fn percent_encodes_query() {
+        let url = WebhookUrl::from_parts(1u64, "t").with_query("a b", "x&y=z/é");
+        assert_eq!(url.endpoint("", &[]), "https://discord.com/api/webhooks/1/t?a%20b=x%26y%3Dz%2F%C3%A9");
+        let reparsed = parse(&url.endpoint("", &[]));
+        assert_eq!(reparsed.query("a b"), Some("x&y=z/é"));
+    }
+
+    #[test]
+    fn percent_decode_keeps_malformed_escapes() {
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz%4"), "%zz%4");
+    }
+
+    #[test]
+    fn redacts_token() {
+        let url = parse("https://discord.com/api/webhooks/1234/secret");
+        assert!(!url.to_string().contains("secret"));
+        assert!(!format!("{:?}", url).contains("secret"));
+    }
+}
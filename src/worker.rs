@@ -0,0 +1,364 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex},
+    task::{Context, Poll},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{anyhow, bail};
+use esp_idf_svc::http::client::EspHttpConnection;
+
+use super::{client::DEFAULT_STACK_SIZE, device, oneshot, MessagePacket, WebhookBuilder, WebhookResponse};
+
+/// What [WebhookWorker::send_message] does when the queue is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Block the caller until there is room in the queue.
+    Block,
+    /// Drop the oldest queued message to make room, its [SendHandle] resolves to an error.
+    DropOldest,
+    /// Refuse the new message and return an error right away.
+    Reject,
+}
+
+/// Settings for a [WebhookWorker].
+///
+/// The default queues up to 8 messages, rejects new ones when full and runs the worker on a 16 KiB stack.
+#[derive(Clone, Debug)]
+pub struct WorkerConfig {
+    /// How many messages may wait in the queue, 0 is treated as 1.
+    pub capacity: usize,
+    pub backpressure: Backpressure,
+    pub stack_size: usize,
+}
+
+impl WorkerConfig {
+    /// Constructs the default worker config.
+    pub fn new() -> Self {
+        Self {
+            capacity: 8,
+            backpressure: Backpressure::Reject,
+            stack_size: DEFAULT_STACK_SIZE,
+        }
+    }
+
+    /// Sets how many messages may wait in the queue.
+    ///
+    /// # Panics
+    /// Will panic if `capacity` is 0.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "worker queue capacity must be at least 1");
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets what happens when a message is queued while the queue is full.
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+
+    /// Sets the stack size of the worker thread.
+    pub fn with_stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A request run on the worker thread, with the connection the worker keeps open.
+///
+/// Dropping it unrun drops the sender it holds, which resolves its handle with an error.
+type Job = Box<dyn FnOnce(&WebhookBuilder, &mut Option<EspHttpConnection>) + Send>;
+
+struct Queue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+}
+
+struct QueueState {
+    jobs: VecDeque<Job>,
+    closed: bool,
+}
+
+/// Sends messages from a background thread, fed through a bounded queue.
+///
+/// Queuing a message never touches the network, so it is cheap enough for tight control loops. The returned [SendHandle]
+/// can be ignored, waited on, or awaited to find out how the request went. The worker keeps its connection to discord
+/// open between messages, reconnecting after a failed request.
+///
+/// Dropping the worker stops it once the messages already queued have been sent.
+///
+/// # Example
+/// ```no_run
+/// use diswh_esp::{Backpressure, MessageBuilder, WebhookBuilder, WebhookWorker, WorkerConfig};
+///
+/// let worker = WebhookWorker::spawn(
+///     WebhookBuilder::new("https://discord.com/api/webhooks/1234/token"),
+///     WorkerConfig::new().with_backpressure(Backpressure::DropOldest),
+/// )?;
+///
+/// worker.send_message(MessageBuilder::new("Hello webhook!", false).build())?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct WebhookWorker {
+    queue: Arc<Queue>,
+    config: WorkerConfig,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WebhookWorker {
+    /// Starts the worker thread.
+    pub fn spawn(webhook: WebhookBuilder, mut config: WorkerConfig) -> anyhow::Result<Self> {
+        // A queue that holds nothing would block or drop messages forever.
+        config.capacity = config.capacity.max(1);
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                jobs: VecDeque::with_capacity(config.capacity),
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        });
+        let worker_queue = queue.clone();
+        let thread = thread::Builder::new()
+            .name("diswh-worker".into())
+            .stack_size(config.stack_size)
+            .spawn(move || run(webhook, worker_queue))?;
+        Ok(Self {
+            queue,
+            config,
+            thread: Some(thread),
+        })
+    }
+
+    /// Queues a message to be sent by the worker.
+    ///
+    /// When the queue is full this blocks, drops the oldest message or fails, depending on the configured [Backpressure].
+    pub fn send_message(&self, packet: MessagePacket) -> anyhow::Result<SendHandle> {
+        let receiver = self.submit(move |webhook, connection| webhook.send_message_on(connection, &packet))?;
+        Ok(SendHandle { receiver })
+    }
+
+    /// Queues any request to run on the worker thread, subject to the configured [Backpressure].
+    pub(crate) fn submit<T, F>(&self, request: F) -> anyhow::Result<oneshot::Receiver<anyhow::Result<T>>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&WebhookBuilder, &mut Option<EspHttpConnection>) -> anyhow::Result<T> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let mut state = self.queue.state.lock().unwrap();
+        loop {
+            if state.closed {
+                bail!("webhook worker has stopped");
+            }
+            if state.jobs.len() < self.config.capacity {
+                break;
+            }
+            match self.config.backpressure {
+                Backpressure::Block => state = self.queue.changed.wait(state).unwrap(),
+                Backpressure::DropOldest => {
+                    // Dropping the job drops its sender, which resolves its handle with an error.
+                    if state.jobs.pop_front().is_none() {
+                        break;
+                    }
+                }
+                Backpressure::Reject => bail!("webhook worker queue is full"),
+            }
+        }
+        state
+            .jobs
+            .push_back(Box::new(move |webhook, connection| sender.send(request(webhook, connection))));
+        drop(state);
+        self.queue.changed.notify_all();
+        Ok(receiver)
+    }
+
+    /// Returns how many messages are waiting to be sent.
+    pub fn queued(&self) -> usize {
+        self.queue.state.lock().unwrap().jobs.len()
+    }
+
+    /// Stops the worker and waits for the messages already queued to be sent.
+    pub fn shutdown(mut self) {
+        self.close();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+
+    fn close(&self) {
+        self.queue.state.lock().unwrap().closed = true;
+        self.queue.changed.notify_all();
+    }
+}
+
+impl Drop for WebhookWorker {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn run(webhook: WebhookBuilder, queue: Arc<Queue>) {
+    // Kept open between messages, so only the first one pays for the TLS handshake.
+    let mut connection = None;
+    loop {
+        let job = {
+            let mut state = queue.state.lock().unwrap();
+            while state.jobs.is_empty() && !state.closed {
+                state = queue.changed.wait(state).unwrap();
+            }
+            match state.jobs.pop_front() {
+                Some(job) => job,
+                None => return,
+            }
+        };
+        // Wake up anyone blocked on a full queue.
+        queue.changed.notify_all();
+        job(&webhook, &mut connection);
+        log::debug!("webhook worker stack headroom: {} bytes", device::stack_headroom());
+    }
+}
+
+/// The pending result of a message queued on a [WebhookWorker].
+///
+/// Resolves to an error if the message was dropped from the queue before it could be sent.
+pub struct SendHandle {
+    receiver: oneshot::Receiver<anyhow::Result<WebhookResponse>>,
+}
+
+impl SendHandle {
+    /// Blocks until the message has been sent.
+    pub fn wait(self) -> anyhow::Result<WebhookResponse> {
+        self.receiver
+            .wait()
+            .ok_or_else(|| anyhow!("message was dropped before it was sent"))?
+    }
+
+    /// Returns true once the message has been sent or dropped.
+    pub fn is_finished(&self) -> bool {
+        self.receiver.is_ready()
+    }
+}
+
+impl Future for SendHandle {
+    type Output = anyhow::Result<WebhookResponse>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|result| result.ok_or_else(|| anyhow!("message was dropped before it was sent"))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::*;
+
+    fn config(capacity: usize, backpressure: Backpressure) -> WorkerConfig {
+        WorkerConfig {
+            capacity,
+            backpressure,
+            ..WorkerConfig::new()
+        }
+    }
+
+    /// Starts a worker that is busy with a job until the returned sender is used or dropped.
+    fn busy_worker(capacity: usize, backpressure: Backpressure) -> (WebhookWorker, mpsc::Sender<()>) {
+        let worker = WebhookWorker::spawn(
+            WebhookBuilder::new("https://discord.com/api/webhooks/1/t"),
+            config(capacity, backpressure),
+        )
+        .unwrap();
+        let (gate, wait) = mpsc::channel();
+        let (started, has_started) = mpsc::channel();
+        worker
+            .submit(move |_, _| {
+                started.send(()).unwrap();
+                let _ = wait.recv();
+                Ok(())
+            })
+            .unwrap();
+        has_started.recv().unwrap();
+        assert_eq!(worker.queued(), 0);
+        (worker, gate)
+    }
+
+    #[test]
+    fn zero_capacity_is_treated_as_one() {
+        for backpressure in [Backpressure::Block, Backpressure::DropOldest, Backpressure::Reject] {
+            let worker = WebhookWorker::spawn(
+                WebhookBuilder::new("https://discord.com/api/webhooks/1/t"),
+                config(0, backpressure),
+            )
+            .unwrap();
+            assert_eq!(worker.config.capacity, 1);
+            let receiver = worker.submit(move |_, _| Ok(backpressure)).unwrap();
+            assert_eq!(receiver.wait().unwrap().unwrap(), backpressure);
+            worker.shutdown();
+        }
+    }
+
+    #[test]
+    fn reject_refuses_jobs_while_full() {
+        let (worker, gate) = busy_worker(1, Backpressure::Reject);
+        let queued = worker.submit(|_, _| Ok("queued")).unwrap();
+        let Err(error) = worker.submit(|_, _| Ok("rejected")) else {
+            panic!("a full queue accepted a job");
+        };
+        assert_eq!(error.to_string(), "webhook worker queue is full");
+        assert_eq!(worker.queued(), 1);
+
+        gate.send(()).unwrap();
+        assert_eq!(queued.wait().unwrap().unwrap(), "queued");
+        worker.shutdown();
+    }
+
+    #[test]
+    fn drop_oldest_resolves_the_dropped_job_to_none() {
+        let (worker, gate) = busy_worker(1, Backpressure::DropOldest);
+        let oldest = worker.submit(|_, _| Ok("oldest")).unwrap();
+        let newest = worker.submit(|_, _| Ok("newest")).unwrap();
+        assert!(oldest.is_ready());
+        assert!(oldest.wait().is_none());
+
+        gate.send(()).unwrap();
+        assert_eq!(newest.wait().unwrap().unwrap(), "newest");
+        worker.shutdown();
+    }
+
+    #[test]
+    fn block_waits_for_room_in_the_queue() {
+        let (worker, gate) = busy_worker(1, Backpressure::Block);
+        let first = worker.submit(|_, _| Ok("first")).unwrap();
+        thread::scope(|scope| {
+            let blocked = scope.spawn(|| worker.submit(|_, _| Ok("second")).unwrap());
+            thread::sleep(Duration::from_millis(50));
+            assert!(!blocked.is_finished());
+            assert_eq!(worker.queued(), 1);
+
+            gate.send(()).unwrap();
+            let second = blocked.join().unwrap();
+            assert_eq!(first.wait().unwrap().unwrap(), "first");
+            assert_eq!(second.wait().unwrap().unwrap(), "second");
+        });
+        worker.shutdown();
+    }
+
+    #[test]
+    fn shutdown_runs_the_jobs_already_queued() {
+        let (worker, gate) = busy_worker(2, Backpressure::Reject);
+        let queued = worker.submit(|_, _| Ok(1)).unwrap();
+        drop(gate);
+        worker.shutdown();
+        assert_eq!(queued.wait().unwrap().unwrap(), 1);
+    }
+}